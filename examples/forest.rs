@@ -0,0 +1,105 @@
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use raydeon::shapes::{Instance, Polyhedron, Prototype, RectPrism};
+use raydeon::{Camera, Scene, Shape, WPoint3, WVec3, WWTransform, WorldSpace};
+
+const ROWS: usize = 12;
+const COLUMNS: usize = 12;
+
+fn main() -> Result<()> {
+    env_logger::Builder::from_default_env()
+        .format_timestamp_nanos()
+        .init();
+
+    // A single tree, which every tree of the forest is an instance of.
+    let tree = Arc::new(Prototype::new(vec![
+        Box::new(RectPrism::new(
+            WVec3::new(-0.1, -0.1, 0.0),
+            WVec3::new(0.1, 0.1, 0.6),
+        )),
+        Box::new(Polyhedron::geodesic_sphere(
+            WPoint3::new(0.0, 0.0, 1.2),
+            0.6,
+            2,
+        )),
+    ]));
+
+    let mut geometry: Vec<Box<dyn Shape<WorldSpace>>> = Vec::new();
+    for row in 0..ROWS {
+        for column in 0..COLUMNS {
+            // Vary the trees a little, without needing a random number generator.
+            let seed = (row * 7 + column * 13) % 10;
+            let height = 0.8 + seed as f64 * 0.06;
+            let angle = euclid::Angle::degrees(seed as f64 * 36.0);
+            let transform = WWTransform::scale(1.0, 1.0, height)
+                .then_rotate(0.0, 0.0, 1.0, angle)
+                .then_translate(WVec3::new(
+                    row as f64 * 2.0 + (seed % 3) as f64 * 0.3,
+                    column as f64 * 2.0,
+                    0.0,
+                ));
+            let instance = Instance::new(Arc::clone(&tree), transform)
+                .context("Tree transformations are invertible")?;
+            geometry.push(Box::new(instance));
+        }
+    }
+
+    let scene = Scene::new(geometry);
+
+    let eye = WPoint3::new(-4.0, -6.0, 5.0);
+    let focus = WVec3::new(12.0, 12.0, 0.0);
+    let up = WVec3::new(0.0, 0.0, 1.0);
+
+    let fovy = 50.0;
+    let width = 1024.0;
+    let height = 1024.0;
+    let znear = 0.1;
+    let zfar = 60.0;
+
+    // Large chunks keep rendering fast, and looking for where they become hidden keeps the
+    // outlines of the trees crisp.
+    let camera = Camera::look_at(eye, focus, up)
+        .perspective(fovy, width, height, znear, zfar)
+        .with_chunk_size(16.0)
+        .with_boundary_tolerance(0.25);
+
+    let paths = scene.attach_camera(camera).render();
+
+    // We currently don't have any functionality to aid in emitting SVG images, so you will
+    // be required to use the [svg crate.](https://crates.io/crates/svg)
+    let mut svg_doc = svg::Document::new()
+        .set("width", "8in")
+        .set("height", "8in")
+        .set("viewBox", (0, 0, width, height))
+        .set("stroke-width", "0.4mm")
+        .set("stroke", "black")
+        .set("fill", "none")
+        .add(
+            svg::node::element::Rectangle::new()
+                .set("x", 0)
+                .set("y", 0)
+                .set("width", "100%")
+                .set("height", "100%")
+                .set("fill", "white"),
+        );
+
+    // We have to flip the y-axis in our svg...
+    let mut item_group = svg::node::element::Group::new()
+        .set("transform", format!("translate(0, {}) scale(1,-1)", height));
+
+    for path in paths {
+        let (p1, p2) = (path.p1, path.p2);
+        item_group = item_group.add(
+            svg::node::element::Line::new()
+                .set("x1", p1.x)
+                .set("y1", p1.y)
+                .set("x2", p2.x)
+                .set("y2", p2.y),
+        );
+    }
+
+    svg_doc = svg_doc.add(item_group);
+
+    svg::save("forest.svg", &svg_doc).context("Failed to write svg")
+}
@@ -1,6 +1,7 @@
 use anyhow::{Context, Result};
 use raydeon::shapes::RectPrism;
 use raydeon::{Camera, Scene, Shape, WPoint3, WVec3, WorldSpace};
+use std::time::{Duration, Instant};
 
 fn main() -> Result<()> {
     env_logger::Builder::from_default_env()
@@ -20,9 +21,21 @@ fn main() -> Result<()> {
     let zfar = 100.0;
 
     let scene = Scene::new(generate_scene());
+    log::info!("Bounding volume hierarchy: {}", scene.bvh().stats());
+
+    // Pass a depth to draw the nodes of the bounding volume hierarchy down to it over the scene.
+    let bvh_depth = match std::env::args().nth(1) {
+        Some(depth) => Some(depth.parse::<usize>().context("Invalid depth")?),
+        None => None,
+    };
 
     let camera = Camera::look_at(eye, focus, up).perspective(fovy, width, height, znear, zfar);
 
+    // Set TIME_QUERIES to time how fast the scene answers picking and visibility queries.
+    if std::env::var_os("TIME_QUERIES").is_some() {
+        time_queries(&scene, &camera);
+    }
+
     let paths = scene.attach_camera(camera).render();
 
     // We currently don't have any functionality to aid in emitting SVG images, so you will
@@ -58,11 +71,86 @@ fn main() -> Result<()> {
         );
     }
 
+    if let Some(depth) = bvh_depth {
+        let mut bvh_group = svg::node::element::Group::new()
+            .set("stroke", "red")
+            .set("stroke-width", "0.1mm");
+        for path in scene.bvh().debug_paths(..=depth) {
+            for path in camera.project_segment(&path) {
+                let (p1, p2) = (path.p1, path.p2);
+                bvh_group = bvh_group.add(
+                    svg::node::element::Line::new()
+                        .set("x1", p1.x)
+                        .set("y1", p1.y)
+                        .set("x2", p2.x)
+                        .set("y2", p2.y),
+                );
+            }
+        }
+        item_group = item_group.add(bvh_group);
+    }
+
     svg_doc = svg_doc.add(item_group);
 
     svg::save("geom_perf.svg", &svg_doc).context("Failed to write svg")
 }
 
+/// How many rays are cast across each side of the canvas when timing queries.
+const QUERY_GRID: usize = 400;
+/// How many times each kind of query is timed. The fastest run is reported, as it's the one
+/// least disturbed by whatever else the machine is doing.
+const QUERY_RUNS: usize = 25;
+
+/// Times picking through every point of a grid over the canvas, and testing the visibility of
+/// the points which were hit from the eye, to measure how fast the scene answers ray queries.
+fn time_queries(scene: &Scene, camera: &Camera) {
+    let step = (
+        camera.width() / QUERY_GRID as f64,
+        camera.height() / QUERY_GRID as f64,
+    );
+    let pixels: Vec<(f64, f64)> = (0..QUERY_GRID * QUERY_GRID)
+        .map(|i| {
+            let (x, y) = (i % QUERY_GRID, i / QUERY_GRID);
+            ((x as f64 + 0.5) * step.0, (y as f64 + 0.5) * step.1)
+        })
+        .collect();
+    let hits: Vec<WPoint3> = pixels
+        .iter()
+        .filter_map(|&(x, y)| scene.pick(camera, x, y))
+        .map(|hit| hit.hit_point)
+        .collect();
+
+    let fastest = |query: &dyn Fn() -> usize| {
+        (0..QUERY_RUNS)
+            .map(|_| {
+                let start = Instant::now();
+                std::hint::black_box(query());
+                start.elapsed()
+            })
+            .min()
+            .unwrap_or(Duration::ZERO)
+    };
+    let picking = fastest(&|| {
+        pixels
+            .iter()
+            .filter(|&&(x, y)| scene.pick(camera, x, y).is_some())
+            .count()
+    });
+    let visibility = fastest(&|| {
+        hits.iter()
+            .filter(|&&point| scene.visible(camera.eye(), point))
+            .count()
+    });
+    log::info!(
+        "Picking {} rays took {:.1?}, testing the visibility of {} points took {:.1?} (best of {})",
+        pixels.len(),
+        picking,
+        hits.len(),
+        visibility,
+        QUERY_RUNS
+    );
+}
+
 const WIDTH: usize = 100;
 const LENGTH: usize = 100;
 
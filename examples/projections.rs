@@ -0,0 +1,124 @@
+use anyhow::{bail, Context, Result};
+use raydeon::shapes::{Capsule, RectPrism, RoundedBox};
+use raydeon::{Axonometry, Camera, Projection, Scene, WPoint3, WVec3};
+
+fn main() -> Result<()> {
+    env_logger::Builder::from_default_env()
+        .format_timestamp_nanos()
+        .init();
+
+    // A bracket holding a rod, drawn the way it would be in a technical drawing: with parallel
+    // lines of sight, so that parallel edges stay parallel on the canvas.
+    let scene = Scene::new(vec![
+        Box::new(RoundedBox::new(
+            WVec3::new(-1.5, -1.0, -0.25),
+            WVec3::new(1.5, 1.0, 0.0),
+            0.1,
+        )),
+        Box::new(RectPrism::new(
+            WVec3::new(-1.5, -1.0, 0.0),
+            WVec3::new(-1.0, 1.0, 1.5),
+        )),
+        Box::new(RectPrism::new(
+            WVec3::new(1.0, -1.0, 0.0),
+            WVec3::new(1.5, 1.0, 1.5),
+        )),
+        Box::new(Capsule::new(
+            WPoint3::new(-2.0, 0.0, 1.0),
+            WPoint3::new(2.0, 0.0, 1.0),
+            0.25,
+        )),
+    ]);
+
+    let eye = WPoint3::new(4.0, 3.0, 2.0);
+    let focus = WVec3::new(0.0, 0.0, 0.6);
+    let up = WVec3::new(0.0, 0.0, 1.0);
+
+    let view_width = 5.0;
+    let view_height = 5.0;
+    let width = 1024.0;
+    let height = 1024.0;
+    let znear = 0.1;
+    let zfar = 10.0;
+
+    // Pass the name of a projection to draw the bracket in another style.
+    let orthographic = Projection::Orthographic {
+        view_width,
+        view_height,
+    };
+    let axonometric = |axonometry| Camera::axonometric(focus, up, axonometry, 5.0);
+    let front = || Camera::look_at(WPoint3::new(0.0, -5.0, 0.6), focus, up);
+    let (looking, projection) = match std::env::args().nth(1).as_deref() {
+        None | Some("orthographic") => (Camera::look_at(eye, focus, up), orthographic),
+        Some("isometric") => (axonometric(Axonometry::Isometric), orthographic),
+        Some("dimetric") => (
+            axonometric(Axonometry::Dimetric { elevation: 30.0 }),
+            orthographic,
+        ),
+        Some("trimetric") => (
+            axonometric(Axonometry::Trimetric {
+                azimuth: 30.0,
+                elevation: 25.0,
+            }),
+            orthographic,
+        ),
+        Some("cavalier") => (front(), Projection::cavalier(view_width, view_height, 45.0)),
+        Some("cabinet") => (front(), Projection::cabinet(view_width, view_height, 30.0)),
+        Some("fisheye") => (
+            Camera::look_at(WPoint3::new(2.0, 1.5, 1.0), focus, up),
+            Projection::Fisheye { fov: 180.0 },
+        ),
+        Some("stereographic") => (
+            Camera::look_at(WPoint3::new(2.0, 1.5, 1.0), focus, up),
+            Projection::Stereographic { fov: 180.0 },
+        ),
+        Some("panorama") => (
+            Camera::look_at(WPoint3::new(0.0, 0.0, 0.4), WVec3::new(0.0, -1.0, 0.4), up),
+            Projection::Equirectangular {
+                fovx: 360.0,
+                fovy: 180.0,
+            },
+        ),
+        Some(other) => bail!("Unknown projection {}", other),
+    };
+    let camera = looking.with_projection(projection, width, height, znear, zfar);
+
+    let paths = scene.attach_camera(camera).render();
+
+    // We currently don't have any functionality to aid in emitting SVG images, so you will
+    // be required to use the [svg crate.](https://crates.io/crates/svg)
+    let mut svg_doc = svg::Document::new()
+        .set("width", "8in")
+        .set("height", "8in")
+        .set("viewBox", (0, 0, width, height))
+        .set("stroke-width", "0.7mm")
+        .set("stroke", "black")
+        .set("fill", "none")
+        .add(
+            svg::node::element::Rectangle::new()
+                .set("x", 0)
+                .set("y", 0)
+                .set("width", "100%")
+                .set("height", "100%")
+                .set("fill", "white"),
+        );
+
+    // We have to flip the y-axis in our svg...
+    let mut item_group = svg::node::element::Group::new()
+        .set("transform", format!("translate(0, {}) scale(1,-1)", height));
+
+    for path in paths {
+        let (p1, p2) = (path.p1, path.p2);
+        item_group = item_group.add(
+            svg::node::element::Line::new()
+                .set("x1", p1.x)
+                .set("y1", p1.y)
+                .set("x2", p2.x)
+                .set("y2", p2.y),
+        );
+    }
+
+    svg_doc = svg_doc.add(item_group);
+
+    svg::save("projections.svg", &svg_doc).context("Failed to write svg")
+}
@@ -0,0 +1,92 @@
+use anyhow::{Context, Result};
+use raydeon::shapes::{Heightmap, HeightmapStyle};
+use raydeon::{Camera, Scene, WPoint3, WVec3};
+
+fn main() -> Result<()> {
+    env_logger::Builder::from_default_env()
+        .format_timestamp_nanos()
+        .init();
+
+    // Pass the path to a grayscale PGM or PNG image to draw it, or get a procedural landscape.
+    let terrain = match std::env::args().nth(1) {
+        Some(path) => Heightmap::load(&path, WPoint3::new(-1.0, -1.0, 0.0), 2.0 / 127.0, 0.5)
+            .with_context(|| format!("Failed to load heightmap from {}", path))?,
+        None => generate_terrain(),
+    };
+    let scene = Scene::new(vec![Box::new(
+        terrain.with_style(HeightmapStyle::CrossSectionsY { spacing: 0.04 }),
+    )]);
+
+    let eye = WPoint3::new(0.0, -3.0, 1.5);
+    let focus = WVec3::new(0.0, 0.0, 0.0);
+    let up = WVec3::new(0.0, 0.0, 1.0);
+
+    let fovy = 50.0;
+    let width = 1024.0;
+    let height = 1024.0;
+    let znear = 0.1;
+    let zfar = 10.0;
+
+    let camera = Camera::look_at(eye, focus, up).perspective(fovy, width, height, znear, zfar);
+
+    let paths = scene.attach_camera(camera).render();
+
+    // We currently don't have any functionality to aid in emitting SVG images, so you will
+    // be required to use the [svg crate.](https://crates.io/crates/svg)
+    let mut svg_doc = svg::Document::new()
+        .set("width", "8in")
+        .set("height", "8in")
+        .set("viewBox", (0, 0, width, height))
+        .set("stroke-width", "0.7mm")
+        .set("stroke", "black")
+        .set("fill", "none")
+        .add(
+            svg::node::element::Rectangle::new()
+                .set("x", 0)
+                .set("y", 0)
+                .set("width", "100%")
+                .set("height", "100%")
+                .set("fill", "white"),
+        );
+
+    // We have to flip the y-axis in our svg...
+    let mut item_group = svg::node::element::Group::new()
+        .set("transform", format!("translate(0, {}) scale(1,-1)", height));
+
+    for path in paths {
+        let (p1, p2) = (path.p1, path.p2);
+        item_group = item_group.add(
+            svg::node::element::Line::new()
+                .set("x1", p1.x)
+                .set("y1", p1.y)
+                .set("x2", p2.x)
+                .set("y2", p2.y),
+        );
+    }
+
+    svg_doc = svg_doc.add(item_group);
+
+    svg::save("terrain.svg", &svg_doc).context("Failed to write svg")
+}
+
+fn generate_terrain() -> Heightmap {
+    const SAMPLES: usize = 128;
+
+    let samples = (0..SAMPLES * SAMPLES)
+        .map(|ndx| {
+            let x = (ndx % SAMPLES) as f64 / SAMPLES as f64;
+            let y = (ndx / SAMPLES) as f64 / SAMPLES as f64;
+            let peak = (-((x - 0.5).powi(2) + (y - 0.6).powi(2)) * 12.0).exp();
+            let ridges = 0.1 * (x * 23.0).sin() * (y * 17.0).cos();
+            (peak + ridges).max(0.0)
+        })
+        .collect();
+
+    Heightmap::new(
+        WPoint3::new(-1.0, -1.0, 0.0),
+        [SAMPLES, SAMPLES],
+        samples,
+        2.0 / (SAMPLES - 1) as f64,
+        0.5,
+    )
+}
@@ -1,8 +1,132 @@
-use crate::{HitData, Ray, Shape, WorldSpace, AABB};
+//! A bounding volume hierarchy, for finding the shapes which lie along a ray or within a region
+//! of space without testing every single one of them.
+use crate::path::LineSegment;
+use crate::{Camera, HitData, Ray, Shape, ShapeId, WVec3, WorldSpace, AABB};
 use euclid::Point3D;
 use rayon::prelude::*;
+use std::fmt;
+use std::ops::RangeBounds;
 use std::sync::Arc;
-use tracing::info;
+use std::time::Instant;
+use tracing::{debug, info, info_span, warn};
+
+/// The estimated cost of visiting a node of the tree, used by the surface area heuristic.
+const TRAVERSAL_COST: f64 = 1.0;
+/// The estimated cost of intersecting a ray with a single shape.
+const INTERSECTION_COST: f64 = 1.0;
+
+/// How deep the tree may grow. This bounds the size of the stack used to traverse it.
+const MAX_DEPTH: usize = 32;
+
+/// How much the estimated cost of tracing rays through the tree may grow as shapes are moved,
+/// inserted and removed, relative to its cost when it was last built, before it is rebuilt.
+const REBUILD_THRESHOLD: f64 = 1.5;
+
+/// Nodes with at least this many shapes build their children on separate threads. Below it, the
+/// overhead of handing work to another thread outweighs the gain.
+const PARALLEL_BUILD_THRESHOLD: usize = 1024;
+
+/// How much of a degenerate shape's debug output is included in diagnostics about it. Shapes
+/// such as heightmaps would otherwise flood the log.
+const DESCRIPTION_LENGTH: usize = 120;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// How a bounding volume hierarchy decides where to split its nodes.
+pub enum BVHStrategy {
+    /// Split at the median of the shapes' bounding boxes, along the axis which best divides them.
+    /// This is fast to build, but struggles with scenes whose shapes vary greatly in size.
+    Median,
+    /// Split wherever the surface area heuristic estimates that rays will be cheapest to trace,
+    /// trying `bins - 1` evenly spaced planes along each axis.
+    Sah { bins: usize },
+}
+
+impl Default for BVHStrategy {
+    fn default() -> Self {
+        BVHStrategy::Sah { bins: 16 }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// Statistics about the shape of a tree, for finding out why tracing rays through it is slow.
+pub struct BVHStats {
+    /// The number of levels of nodes below the root.
+    pub depth: usize,
+    /// The number of nodes in the tree, including leaves.
+    pub nodes: usize,
+    /// How many leaves hold each number of shapes, e.g. `leaf_sizes[3]` leaves hold three shapes.
+    pub leaf_sizes: Vec<usize>,
+    /// How many more times shapes are referenced by leaves than there are shapes with bounding
+    /// boxes. Shapes which straddle the split plane of a node are put into both of its children.
+    pub duplicate_refs: usize,
+    /// The number of shapes without a bounding box, which every query checks.
+    pub unbounded: usize,
+    /// The cost of tracing a ray through the tree which the surface area heuristic estimates, in
+    /// units of intersection tests.
+    pub sah_cost: f64,
+}
+
+impl BVHStats {
+    /// The number of leaves in the tree.
+    pub fn leaves(&self) -> usize {
+        self.leaf_sizes.iter().sum()
+    }
+}
+
+impl fmt::Display for BVHStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} nodes, {} leaves, depth {}, {} duplicate references, {} unbounded shapes, SAH \
+             cost {:.2}",
+            self.nodes,
+            self.leaves(),
+            self.depth,
+            self.duplicate_refs,
+            self.unbounded,
+            self.sah_cost
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// A shape which can't be put into a tree, since it can't be hit reliably, or has no sensible
+/// bounding box.
+pub struct DegenerateShape {
+    /// The id which the shape would have had.
+    pub id: ShapeId,
+    /// The start of the shape's debug output, to tell which shape it is.
+    pub shape: String,
+    /// What's wrong with the shape.
+    pub reason: String,
+}
+
+impl DegenerateShape {
+    fn new<Space>(id: ShapeId, shape: &dyn Shape<Space>, reason: String) -> Self {
+        let mut description = format!("{:?}", shape);
+        if let Some((end, _)) = description.char_indices().nth(DESCRIPTION_LENGTH) {
+            description.truncate(end);
+            description.push_str("...");
+        }
+        DegenerateShape {
+            id,
+            shape: description,
+            reason,
+        }
+    }
+}
+
+impl fmt::Display for DegenerateShape {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Shape {} is degenerate, since {}: {}",
+            self.id.0, self.reason, self.shape
+        )
+    }
+}
+
+impl std::error::Error for DegenerateShape {}
 
 #[derive(Debug, Clone, Copy)]
 pub(crate) enum Axis {
@@ -11,79 +135,612 @@ pub(crate) enum Axis {
     Z,
 }
 
+impl Axis {
+    fn of<Space>(&self, point: Point3D<f64, Space>) -> f64 {
+        match self {
+            Axis::X => point.x,
+            Axis::Y => point.y,
+            Axis::Z => point.z,
+        }
+    }
+}
+
 #[derive(Debug)]
-pub(crate) struct BVHTree<Space>
+/// A bounding volume hierarchy over a list of shapes, which answers spatial queries about them.
+///
+/// Shapes are identified by their position in the list that the tree was built from. Shapes
+/// without a bounding box, such as planes, are kept aside and checked by every query. Degenerate
+/// shapes are left out with a warning, but still use up their ids.
+///
+/// Queries along rays are answered for shapes in `WorldSpace`, since that is where rays live. The
+/// tree can be built and queried by bounding box in any space.
+pub struct BVHTree<Space>
 where
     Space: Copy + Send + Sync + Sized + std::fmt::Debug + 'static,
 {
+    strategy: BVHStrategy,
+    /// Every shape in the tree by id, along with its bounding box. Removing a shape leaves a gap,
+    /// so that the ids of the others stay the same.
+    shapes: Vec<Option<StoredShape<Space>>>,
+    /// The estimated cost of tracing rays through the tree when it was last built.
+    built_cost: f64,
     aabb: AABB<Space>,
-    root: Option<Node<Space>>,
-    unbounded: Vec<Arc<dyn Shape<Space>>>,
+    /// The nodes of the tree in depth-first order, so that the left child of a parent node always
+    /// directly follows it. The root is the first node, if there are any.
+    nodes: Vec<FlatNode>,
+    /// The shapes referenced by leaf nodes, with the shapes of each leaf stored next to each
+    /// other. Shapes which belong to several leaves are stored once for each of them.
+    leaf_shapes: Vec<BoundedShape<Space>>,
+    unbounded: Vec<(ShapeId, Arc<dyn Shape<Space>>)>,
+}
+
+type StoredShape<Space> = (Arc<dyn Shape<Space>>, Option<AABB<Space>>);
+
+#[derive(Debug, Clone, Copy)]
+enum FlatNode {
+    Parent {
+        axis: Axis,
+        point: f64,
+        /// The index of the right child. The left child follows the parent directly.
+        right: u32,
+    },
+    Leaf {
+        start: u32,
+        count: u32,
+    },
 }
 
 impl<Space> BVHTree<Space>
 where
     Space: Copy + Send + Sync + Sized + std::fmt::Debug + 'static,
 {
-    pub(crate) fn new(shapes: &[Arc<dyn Shape<Space>>]) -> Self {
+    /// Builds a tree over the given shapes, using the default strategy, which is the surface area
+    /// heuristic with 16 bins.
+    pub fn new(shapes: &[Arc<dyn Shape<Space>>]) -> Self {
+        Self::with_strategy(shapes, BVHStrategy::default())
+    }
+
+    /// Builds a tree whose nodes are split using the given strategy.
+    pub fn with_strategy(shapes: &[Arc<dyn Shape<Space>>], strategy: BVHStrategy) -> Self {
+        let mut tree = Self {
+            strategy,
+            shapes: Vec::new(),
+            built_cost: 0.0,
+            aabb: AABB::new(Point3D::splat(f64::MAX), Point3D::splat(f64::MIN)),
+            nodes: Vec::new(),
+            leaf_shapes: Vec::new(),
+            unbounded: Vec::new(),
+        };
+        tree.shapes = stored_shapes(0, shapes);
+        tree.rebuild();
+        tree
+    }
+
+    /// Builds the tree from scratch, over the shapes which it currently holds.
+    pub fn rebuild(&mut self) {
+        let span = info_span!("bvh_build", shapes = self.shapes.len(), strategy = ?self.strategy);
+        let _guard = span.enter();
+        let start = Instant::now();
         info!(
             "Creating Bounded Volume Hierarchy for {} shapes",
-            shapes.len()
+            self.shapes.len()
         );
-        let mut bounded = Vec::with_capacity(shapes.len());
-        let mut unbounded = Vec::with_capacity(shapes.len());
-
-        for shape in shapes.iter() {
-            let aabb = shape.bounding_box();
 
+        let mut bounded = Vec::with_capacity(self.shapes.len());
+        let mut unbounded = Vec::with_capacity(self.shapes.len());
+        for (id, shape, aabb) in self.stored() {
             let shape = Arc::clone(shape);
             match aabb {
-                Some(aabb) => bounded.push(Arc::new(BoundedShape { aabb, shape })),
-                None => unbounded.push(shape),
+                Some(aabb) => bounded.push(Arc::new(BoundedShape { id, aabb, shape })),
+                None => unbounded.push((id, shape)),
             }
         }
 
-        let aabb = bounding_box_for_shapes(&bounded);
-        let root = (!shapes.is_empty()).then(|| {
-            let (root, depth) = Node::new(bounded);
-            info!("Created Bounded Volume Hierarchy with depth {}", depth);
-            root
+        self.aabb = bounding_box_for_shapes(&bounded);
+        self.nodes.clear();
+        self.leaf_shapes.clear();
+        self.unbounded = unbounded;
+        if self.stored().next().is_some() {
+            let (root, depth) = Node::new(bounded, self.aabb, self.strategy, 0);
+            root.flatten(&mut self.nodes, &mut self.leaf_shapes);
+            info!(
+                "Created Bounded Volume Hierarchy with depth {} in {:?}",
+                depth,
+                start.elapsed()
+            );
+        }
+        self.built_cost = self.cost();
+    }
+
+    /// Adds shapes to the tree, returning the ids they were given. Ids of removed shapes are not
+    /// reused.
+    pub fn insert(&mut self, shapes: &[Arc<dyn Shape<Space>>]) -> Vec<ShapeId> {
+        let ids: Vec<_> = (self.shapes.len()..self.shapes.len() + shapes.len())
+            .map(ShapeId)
+            .collect();
+        self.shapes.extend(stored_shapes(self.shapes.len(), shapes));
+        self.rebucket_ids(&ids);
+        ids
+    }
+
+    /// Removes the shapes with the given ids from the tree. Ids of shapes which aren't in the
+    /// tree are ignored.
+    pub fn remove(&mut self, ids: &[ShapeId]) {
+        let mut removed = Vec::with_capacity(ids.len());
+        for id in ids {
+            if let Some(stored) = self.shapes.get_mut(id.0) {
+                *stored = None;
+                removed.push(*id);
+            }
+        }
+        self.rebucket_ids(&removed);
+    }
+
+    /// Replaces shapes which have moved since they were put into the tree by their new versions,
+    /// and re-buckets them: each is taken out of the leaves it was in and put into the leaves
+    /// which its new bounding box overlaps. The split planes of the nodes stay where they are, so
+    /// shapes which move across them, or grow, may end up in more leaves than before. Passing a
+    /// shape which is already in the tree is fine, e.g. for shapes which move on their own. Ids
+    /// of shapes which aren't in the tree are ignored.
+    ///
+    /// This is much faster than building a new tree when only some of the shapes move, or when
+    /// they only move a little. If the tree has gotten too much worse than a new one would be,
+    /// it's rebuilt. Shapes which have become degenerate are removed, and returned.
+    pub fn rebucket(&mut self, moved: &[(ShapeId, Arc<dyn Shape<Space>>)]) -> Vec<DegenerateShape> {
+        let checked: Vec<_> = moved
+            .par_iter()
+            .map(|(id, shape)| check_shape(*id, shape.as_ref()))
+            .collect();
+        let mut ids = Vec::with_capacity(moved.len());
+        let mut degenerate = Vec::new();
+        for ((id, shape), checked) in moved.iter().zip(checked) {
+            if let Some(old @ Some(_)) = self.shapes.get_mut(id.0) {
+                *old = match checked {
+                    Ok(aabb) => Some((Arc::clone(shape), aabb)),
+                    Err(err) => {
+                        degenerate.push(err);
+                        None
+                    }
+                };
+                ids.push(*id);
+            }
+        }
+        self.rebucket_ids(&ids);
+        degenerate
+    }
+
+    /// The shape with the given id, if it's in the tree.
+    pub fn shape(&self, id: ShapeId) -> Option<&Arc<dyn Shape<Space>>> {
+        self.shapes.get(id.0)?.as_ref().map(|(shape, _)| shape)
+    }
+
+    /// All shapes in the tree, along with their ids.
+    pub fn shapes(&self) -> impl Iterator<Item = (ShapeId, &Arc<dyn Shape<Space>>)> {
+        self.stored().map(|(id, shape, _)| (id, shape))
+    }
+
+    fn stored(
+        &self,
+    ) -> impl Iterator<Item = (ShapeId, &Arc<dyn Shape<Space>>, Option<AABB<Space>>)> {
+        self.shapes.iter().enumerate().filter_map(|(ndx, stored)| {
+            stored
+                .as_ref()
+                .map(|(shape, aabb)| (ShapeId(ndx), shape, *aabb))
+        })
+    }
+
+    /// Takes the shapes with the given ids out of the leaves which they were in, and puts their
+    /// current versions, if any, into the leaves which their bounding boxes now overlap. The
+    /// split planes of the nodes stay where they are, while the bounds of the whole tree are fit
+    /// to the shapes again.
+    fn rebucket_ids(&mut self, ids: &[ShapeId]) {
+        if ids.is_empty() {
+            return;
+        }
+        if self.nodes.is_empty() {
+            self.rebuild();
+            return;
+        }
+
+        let mut changed = vec![false; self.shapes.len()];
+        let mut ids = ids.to_vec();
+        ids.retain(|id| !std::mem::replace(&mut changed[id.0], true));
+
+        let mut leaves: Vec<Vec<BoundedShape<Space>>> = vec![Vec::new(); self.nodes.len()];
+        for (ndx, node) in self.nodes.iter().enumerate() {
+            if let FlatNode::Leaf { start, count } = *node {
+                leaves[ndx] = self.leaf_shapes[start as usize..(start + count) as usize]
+                    .iter()
+                    .filter(|geom| !changed[geom.id.0])
+                    .cloned()
+                    .collect();
+            }
+        }
+        self.unbounded.retain(|(id, _)| !changed[id.0]);
+
+        for id in &ids {
+            match &self.shapes[id.0] {
+                Some((shape, Some(aabb))) => {
+                    let geom = BoundedShape {
+                        id: *id,
+                        shape: Arc::clone(shape),
+                        aabb: *aabb,
+                    };
+                    for ndx in self.leaves_overlapping(*aabb) {
+                        leaves[ndx].push(geom.clone());
+                    }
+                }
+                Some((shape, None)) => self.unbounded.push((*id, Arc::clone(shape))),
+                None => {}
+            }
+        }
+        self.unbounded.sort_by_key(|(id, _)| *id);
+
+        self.leaf_shapes.clear();
+        for (node, shapes) in self.nodes.iter_mut().zip(leaves) {
+            if let FlatNode::Leaf { start, count } = node {
+                *start = self.leaf_shapes.len() as u32;
+                *count = shapes.len() as u32;
+                self.leaf_shapes.extend(shapes);
+            }
+        }
+        self.aabb = self.stored().filter_map(|(_, _, aabb)| aabb).fold(
+            AABB::new(Point3D::splat(f64::MAX), Point3D::splat(f64::MIN)),
+            |a, b| AABB::new(a.min.min(b.min), a.max.max(b.max)),
+        );
+
+        let cost = self.cost();
+        debug!(
+            "Re-bucketed {} shapes in Bounded Volume Hierarchy, cost went from {} to {}",
+            ids.len(),
+            self.built_cost,
+            cost
+        );
+        if cost > self.built_cost * REBUILD_THRESHOLD {
+            self.rebuild();
+        }
+    }
+
+    /// The indices of the leaf nodes into which a shape with the given bounding box goes, using
+    /// the same rule as when the tree is built.
+    fn leaves_overlapping(&self, aabb: AABB<Space>) -> Vec<usize> {
+        let mut leaves = Vec::new();
+        let mut stack = vec![0u32];
+        while let Some(ndx) = stack.pop() {
+            match self.nodes[ndx as usize] {
+                FlatNode::Parent { axis, point, right } => {
+                    let (left, right_side) = partition_bounding_box(axis, aabb, point);
+                    if left {
+                        stack.push(ndx + 1);
+                    }
+                    if right_side {
+                        stack.push(right);
+                    }
+                }
+                FlatNode::Leaf { .. } => leaves.push(ndx as usize),
+            }
+        }
+        leaves
+    }
+
+    /// The estimated cost of tracing a ray through the tree according to the surface area
+    /// heuristic, relative to the cost of intersecting a single shape.
+    fn cost(&self) -> f64 {
+        let root_area = surface_area(self.aabb);
+        if self.nodes.is_empty() || root_area <= 0.0 || !root_area.is_finite() {
+            return self.leaf_shapes.len() as f64 * INTERSECTION_COST;
+        }
+
+        let mut cost = 0.0;
+        self.walk(|node, bounds, _| {
+            let area = surface_area(bounds) / root_area;
+            cost += match node {
+                FlatNode::Parent { .. } => area * TRAVERSAL_COST,
+                FlatNode::Leaf { count, .. } => area * count as f64 * INTERSECTION_COST,
+            };
         });
-        Self {
-            aabb,
-            unbounded,
-            root,
+        cost
+    }
+
+    /// Visits every node of the tree, along with its bounds and depth.
+    fn walk<F>(&self, mut visit: F)
+    where
+        F: FnMut(FlatNode, AABB<Space>, usize),
+    {
+        if self.nodes.is_empty() {
+            return;
+        }
+        let mut stack = vec![(0u32, self.aabb, 0)];
+        while let Some((ndx, bounds, depth)) = stack.pop() {
+            let node = self.nodes[ndx as usize];
+            // Once shapes have been re-bucketed, split planes may lie outside of the bounds of their
+            // nodes, which leaves the bounds of one of the children inside out.
+            visit(
+                node,
+                AABB::new(bounds.min, bounds.max.max(bounds.min)),
+                depth,
+            );
+            if let FlatNode::Parent { axis, point, right } = node {
+                let (left_bounds, right_bounds) = split_bounds(bounds, axis, point);
+                stack.push((right, right_bounds, depth + 1));
+                stack.push((ndx + 1, left_bounds, depth + 1));
+            }
         }
     }
-}
 
-impl BVHTree<WorldSpace> {
-    pub(crate) fn intersects(&self, ray: Ray) -> Option<HitData> {
+    /// Gathers statistics about the shape of the tree.
+    pub fn stats(&self) -> BVHStats {
+        let mut stats = BVHStats {
+            depth: 0,
+            nodes: self.nodes.len(),
+            leaf_sizes: Vec::new(),
+            duplicate_refs: 0,
+            unbounded: self.unbounded.len(),
+            sah_cost: self.cost(),
+        };
+        self.walk(|node, _, depth| {
+            stats.depth = stats.depth.max(depth);
+            if let FlatNode::Leaf { count, .. } = node {
+                let count = count as usize;
+                if stats.leaf_sizes.len() <= count {
+                    stats.leaf_sizes.resize(count + 1, 0);
+                }
+                stats.leaf_sizes[count] += 1;
+            }
+        });
+        let bounded = self.stored().filter(|(_, _, aabb)| aabb.is_some()).count();
+        stats.duplicate_refs = self.leaf_shapes.len().saturating_sub(bounded);
+        stats
+    }
+
+    /// The edges of the bounds of the nodes at the given depths, tagged with their depth, so that
+    /// the tree can be drawn over the shapes which it holds. The root is at depth 0.
+    pub fn debug_paths<R>(&self, depths: R) -> Vec<LineSegment<Space>>
+    where
+        R: RangeBounds<usize>,
+    {
+        let mut paths = Vec::new();
+        self.walk(|_, bounds, depth| {
+            if depths.contains(&depth) {
+                paths.extend(box_edges(bounds, depth));
+            }
+        });
+        paths
+    }
+
+    /// The box around every shape in the tree, or `None` if it holds no shapes at all or any
+    /// unbounded ones.
+    pub fn bounding_box(&self) -> Option<AABB<Space>> {
+        (!self.nodes.is_empty() && self.unbounded.is_empty()).then_some(self.aabb)
+    }
+
+    /// The box around every shape in the tree which has one, or `None` if none of them do.
+    pub fn bounded_box(&self) -> Option<AABB<Space>> {
+        (!self.nodes.is_empty()).then_some(self.aabb)
+    }
+
+    /// Finds the shapes whose bounding boxes overlap the given box, including boxes which only
+    /// touch it. Shapes without a bounding box are always included.
+    pub fn overlapping(&self, aabb: AABB<Space>) -> Vec<ShapeId> {
+        self.select(|bounds| boxes_overlap(bounds, aabb))
+    }
+
+    /// Finds the shapes whose bounding boxes pass `overlaps`, in order of their ids. `overlaps`
+    /// must tell whether a box overlaps some region of space, since it's also used to skip the
+    /// parts of the tree which lie outside of that region.
+    fn select<F>(&self, overlaps: F) -> Vec<ShapeId>
+    where
+        F: Fn(AABB<Space>) -> bool,
+    {
+        let mut ids: Vec<_> = self.unbounded.iter().map(|(id, _)| *id).collect();
+        if self.nodes.is_empty() || !overlaps(self.aabb) {
+            return ids;
+        }
+
+        let mut stack = [(0u32, self.aabb); MAX_DEPTH];
+        let mut len = 0;
+        let mut node = (0u32, self.aabb);
+
+        loop {
+            let (ndx, bounds) = node;
+            match self.nodes[ndx as usize] {
+                FlatNode::Parent { axis, point, right } => {
+                    let (left_bounds, right_bounds) = split_bounds(bounds, axis, point);
+                    match (overlaps(left_bounds), overlaps(right_bounds)) {
+                        (true, true) => {
+                            stack[len] = (right, right_bounds);
+                            len += 1;
+                            node = (ndx + 1, left_bounds);
+                            continue;
+                        }
+                        (true, false) => {
+                            node = (ndx + 1, left_bounds);
+                            continue;
+                        }
+                        (false, true) => {
+                            node = (right, right_bounds);
+                            continue;
+                        }
+                        (false, false) => {}
+                    }
+                }
+                FlatNode::Leaf { start, count } => {
+                    let shapes = &self.leaf_shapes[start as usize..(start + count) as usize];
+                    ids.extend(
+                        shapes
+                            .iter()
+                            .filter(|geom| overlaps(geom.aabb))
+                            .map(|geom| geom.id),
+                    );
+                }
+            }
+
+            if len == 0 {
+                break;
+            }
+            len -= 1;
+            node = stack[len];
+        }
+
+        // Shapes which straddle the split of a node belong to both of its children.
+        ids.sort_unstable();
+        ids.dedup();
+        ids
+    }
+
+    /// Finds the closest hit along the bounded part of the ray, if any.
+    pub fn closest_hit(&self, ray: Ray) -> Option<HitData> {
         vec![
             self.intersects_bounded_volume(ray),
             self.intersects_unbounded_volume(ray),
         ]
         .into_iter()
         .flatten()
-        .min_by(|hit1, hit2| hit1.dist_to.partial_cmp(&hit2.dist_to).unwrap())
+        .min_by(|hit1, hit2| hit1.dist_to.total_cmp(&hit2.dist_to))
     }
 
-    fn intersects_bounded_volume(&self, ray: Ray) -> Option<HitData> {
-        let (tmin, tmax) = bounding_box_intersects(self.aabb, ray);
-        if tmax < tmin || tmax <= 0.0 {
+    /// Finds any hit along the bounded part of the ray, which need not be the closest one. This
+    /// stops looking as soon as a hit is found, which makes it cheaper than finding the closest
+    /// one when all that matters is whether anything is in the way.
+    pub fn any_hit(&self, ray: Ray) -> Option<HitData> {
+        let unbounded_hit = self
+            .unbounded
+            .iter()
+            .find_map(|(id, geom)| ray.hit(&**geom).map(|hit| hit.with_shape_id(*id)));
+        if unbounded_hit.is_some() {
+            return unbounded_hit;
+        }
+
+        let mut found = None;
+        self.traverse(ray, |geom| {
+            found = ray.hit(&*geom.shape).map(|hit| hit.with_shape_id(geom.id));
+            found.map(|_| 0.0)
+        });
+        found
+    }
+
+    /// Finds every shape which the bounded part of the ray hits, ordered by distance. Only the
+    /// first hit on each shape is reported.
+    pub fn all_hits(&self, ray: Ray) -> Vec<HitData> {
+        let mut hits: Vec<_> = self
+            .unbounded
+            .iter()
+            .filter_map(|(id, geom)| ray.hit(&**geom).map(|hit| hit.with_shape_id(*id)))
+            .collect();
+        self.traverse(ray, |geom| {
+            if let Some(hit) = ray.hit(&*geom.shape) {
+                hits.push(hit.with_shape_id(geom.id));
+            }
             None
-        } else {
-            self.root
-                .as_ref()
-                .and_then(|root| root.intersects(ray, tmin, tmax))
+        });
+
+        // Shapes which straddle the split of a node belong to both of its children.
+        hits.sort_by_key(|hit| hit.shape_id);
+        hits.dedup_by_key(|hit| hit.shape_id);
+        hits.sort_by(|hit1, hit2| hit1.dist_to.total_cmp(&hit2.dist_to));
+        hits
+    }
+
+    /// The part of the ray which lies within the bounding box of all bounded shapes, if any.
+    fn bounded_volume_range(&self, ray: Ray, inv_dir: [f64; 3]) -> Option<(f64, f64)> {
+        let (tmin, tmax) = bounding_box_intersects(self.aabb, ray, inv_dir);
+        let (tmin, tmax) = (tmin.max(ray.tmin), tmax.min(ray.tmax));
+        (tmax >= tmin && tmax > 0.0).then_some((tmin, tmax))
+    }
+
+    fn intersects_bounded_volume(&self, ray: Ray) -> Option<HitData> {
+        let mut closest: Option<HitData> = None;
+        self.traverse(ray, |geom| {
+            let hit = ray.hit(&*geom.shape)?;
+            if closest.is_none_or(|closest| hit.dist_to < closest.dist_to) {
+                closest = Some(hit.with_shape_id(geom.id));
+            }
+            closest.map(|hit| hit.dist_to)
+        });
+        closest
+    }
+
+    /// Visits the shapes of the leaves which the ray passes through, nearest leaf first. `visit`
+    /// returns the distance to the closest hit found so far, if any, and leaves which lie entirely
+    /// beyond that distance are skipped. Returning zero ends the traversal.
+    fn traverse<F>(&self, ray: Ray, mut visit: F)
+    where
+        F: FnMut(&BoundedShape<Space>) -> Option<f64>,
+    {
+        let dir = ray.dir.to_array();
+        let origin = ray.point.to_array();
+        let inv_dir = dir.map(|d| 1.0 / d);
+
+        let (tmin, tmax) = match self.bounded_volume_range(ray, inv_dir) {
+            Some(range) if !self.nodes.is_empty() => range,
+            _ => return,
+        };
+
+        let mut stack = [(0u32, 0.0, 0.0); MAX_DEPTH];
+        let mut len = 0;
+        let mut node = (0u32, tmin, tmax);
+        let mut closest = f64::INFINITY;
+
+        loop {
+            let (ndx, tmin, tmax) = node;
+            match self.nodes[ndx as usize] {
+                FlatNode::Parent { axis, point, right } if closest > tmin => {
+                    let a = axis as usize;
+                    let tsplit = (point - origin[a]) * inv_dir[a];
+                    let left_first = (origin[a] < point) || (origin[a] == point && dir[a] <= 0.0);
+                    let (first, second) = if left_first {
+                        (ndx + 1, right)
+                    } else {
+                        (right, ndx + 1)
+                    };
+
+                    if tsplit > tmax || tsplit <= 0.0 {
+                        node = (first, tmin, tmax);
+                    } else if tsplit < tmin {
+                        node = (second, tmin, tmax);
+                    } else {
+                        // Descend into the nearer child, and come back for the farther one.
+                        stack[len] = (second, tsplit, tmax);
+                        len += 1;
+                        node = (first, tmin, tsplit);
+                    }
+                    continue;
+                }
+                FlatNode::Leaf { start, count } if closest > tmin => {
+                    let shapes = &self.leaf_shapes[start as usize..(start + count) as usize];
+                    for geom in shapes {
+                        if let Some(dist) = visit(geom) {
+                            closest = closest.min(dist);
+                            if closest <= 0.0 {
+                                return;
+                            }
+                        }
+                    }
+                }
+                _ => {}
+            }
+
+            if len == 0 {
+                return;
+            }
+            len -= 1;
+            node = stack[len];
         }
     }
 
     fn intersects_unbounded_volume(&self, ray: Ray) -> Option<HitData> {
         self.unbounded
             .iter()
-            .filter_map(|geom| geom.hit_by(&ray))
-            .min_by(|hit1, hit2| hit1.dist_to.partial_cmp(&hit2.dist_to).unwrap())
+            .filter_map(|(id, geom)| ray.hit(&**geom).map(|hit| hit.with_shape_id(*id)))
+            .min_by(|hit1, hit2| hit1.dist_to.total_cmp(&hit2.dist_to))
+    }
+}
+
+impl BVHTree<WorldSpace> {
+    /// Finds the shapes which may be seen by the camera, as their bounding boxes overlap its view
+    /// frustum. Shapes without a bounding box are always included.
+    pub fn in_frustum(&self, camera: &Camera) -> Vec<ShapeId> {
+        self.select(|bounds| camera.overlaps_frustum(bounds))
     }
 }
 
@@ -107,57 +764,6 @@ where
     right: Box<Node<Space>>,
 }
 
-impl ParentNode<WorldSpace> {
-    fn intersects(&self, ray: Ray, tmin: f64, tmax: f64) -> Option<HitData> {
-        let rp: f64;
-        let rd: f64;
-        match self.axis {
-            Axis::X => {
-                rp = ray.point.x;
-                rd = ray.dir.x;
-            }
-            Axis::Y => {
-                rp = ray.point.y;
-                rd = ray.dir.y;
-            }
-            Axis::Z => {
-                rp = ray.point.z;
-                rd = ray.dir.z;
-            }
-        };
-        let tsplit = (self.point - rp) / rd;
-        let left_first = (rp < self.point) || (rp == self.point && rd <= 0.0);
-
-        let (first, second) = if left_first {
-            (&self.left, &self.right)
-        } else {
-            (&self.right, &self.left)
-        };
-        if tsplit > tmax || tsplit <= 0.0 {
-            first.intersects(ray, tmin, tmax)
-        } else if tsplit < tmin {
-            second.intersects(ray, tmin, tmax)
-        } else {
-            let h1 = first.intersects(ray, tmin, tsplit);
-
-            if h1.is_some_and(|hit| hit.dist_to <= tsplit) {
-                return h1;
-            }
-
-            let h1t = h1.map(|hit| hit.dist_to).unwrap_or(f64::MAX);
-
-            let h2 = second.intersects(ray, tsplit, f64::min(tmax, h1t));
-            let h2t = h2.map(|hit| hit.dist_to).unwrap_or(f64::MAX);
-
-            if h1t < h2t {
-                h1
-            } else {
-                h2
-            }
-        }
-    }
-}
-
 #[derive(Debug)]
 struct LeafNode<Space>
 where
@@ -172,11 +778,11 @@ impl<Space> LeafNode<Space>
 where
     Space: Copy + Send + Sync + Sized + std::fmt::Debug + 'static,
 {
-    fn partition(&self, best: u64, best_axis: Axis, best_point: f64) -> PartitionedSegments<Space> {
-        let mut left = Vec::with_capacity(best as usize);
-        let mut right = Vec::with_capacity(best as usize);
+    fn partition(&self, axis: Axis, point: f64) -> PartitionedSegments<Space> {
+        let mut left = Vec::with_capacity(self.shapes.len());
+        let mut right = Vec::with_capacity(self.shapes.len());
         for shape in &self.shapes {
-            let (l, r) = partition_bounding_box(best_axis, shape.aabb, best_point);
+            let (l, r) = partition_bounding_box(axis, shape.aabb, point);
             if l {
                 left.push(Arc::clone(shape));
             }
@@ -205,37 +811,11 @@ where
             right
         }
     }
-}
-
-impl LeafNode<WorldSpace> {
-    fn intersects(&self, ray: Ray) -> Option<HitData> {
-        self.shapes
-            .iter()
-            .filter_map(|geom| geom.shape.hit_by(&ray))
-            .min_by(|hit1, hit2| hit1.dist_to.partial_cmp(&hit2.dist_to).unwrap())
-    }
-}
-
-impl<Space> Node<Space>
-where
-    Space: Copy + Send + Sync + Sized + std::fmt::Debug + 'static,
-{
-    fn new(shapes: Vec<Arc<BoundedShape<Space>>>) -> (Self, usize) {
-        let mut node = Self::Leaf(LeafNode { shapes });
-        let depth = node.split();
-        (node, depth + 1)
-    }
-
-    fn split(&mut self) -> usize {
-        let leaf = match self {
-            Self::Parent(_) => return 0,
-            Self::Leaf(leaf) => leaf,
-        };
-        let shapes = &leaf.shapes;
-        if shapes.len() < 8 {
-            return 1;
-        }
 
+    /// Splits at the median of the bounding box bounds along whichever axis leaves the fewest
+    /// shapes on the fuller side, as long as that side ends up with less than 85% of them.
+    fn median_split(&self) -> Option<(Axis, f64)> {
+        let shapes = &self.shapes;
         let mut xs = Vec::with_capacity(shapes.len() * 2);
         let mut ys = Vec::with_capacity(shapes.len() * 2);
         let mut zs = Vec::with_capacity(shapes.len() * 2);
@@ -247,70 +827,248 @@ where
             zs.push(shape.aabb.min.z);
             zs.push(shape.aabb.max.z);
         }
-        xs.sort_by(|a, b| a.partial_cmp(b).unwrap());
-        ys.sort_by(|a, b| a.partial_cmp(b).unwrap());
-        zs.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        let sort = |nums: &mut Vec<f64>| nums.par_sort_by(f64::total_cmp);
+        rayon::join(
+            || sort(&mut xs),
+            || rayon::join(|| sort(&mut ys), || sort(&mut zs)),
+        );
 
         let mx = median(&xs);
         let my = median(&ys);
         let mz = median(&zs);
 
         let mut best = (shapes.len() as f64 * 0.85) as u64;
-        let mut best_axis: Option<Axis> = None;
-        let mut best_point = 0.0;
+        let mut best_split = None;
 
-        let sx = leaf.partition_score(Axis::X, mx);
+        let sx = self.partition_score(Axis::X, mx);
         if sx < best {
             best = sx;
-            best_axis = Some(Axis::X);
-            best_point = mx;
+            best_split = Some((Axis::X, mx));
         }
-        let sy = leaf.partition_score(Axis::Y, my);
+        let sy = self.partition_score(Axis::Y, my);
         if sy < best {
             best = sy;
-            best_axis = Some(Axis::Y);
-            best_point = my;
+            best_split = Some((Axis::Y, my));
         }
-        let sz = leaf.partition_score(Axis::Z, mz);
+        let sz = self.partition_score(Axis::Z, mz);
         if sz < best {
-            best = sz;
-            best_axis = Some(Axis::Z);
-            best_point = mz;
+            best_split = Some((Axis::Z, mz));
+        }
+        best_split
+    }
+
+    /// Picks the split plane with the lowest estimated cost according to the surface area
+    /// heuristic, out of `bins - 1` evenly spaced candidates along each axis. Returns `None` if
+    /// no split is expected to be cheaper than leaving the node as a leaf.
+    fn sah_split(&self, bounds: AABB<Space>, bins: usize) -> Option<(Axis, f64)> {
+        let area = surface_area(bounds);
+        if area <= 0.0 || bins < 2 {
+            return None;
+        }
+
+        let leaf_cost = self.shapes.len() as f64 * INTERSECTION_COST;
+        [Axis::X, Axis::Y, Axis::Z]
+            .par_iter()
+            .filter_map(|&axis| self.sah_axis_split(bounds, area, axis, bins))
+            .filter(|(cost, _)| *cost < leaf_cost)
+            .min_by(|(cost1, _), (cost2, _)| cost1.total_cmp(cost2))
+            .map(|(_, split)| split)
+    }
+
+    /// Finds the cheapest split plane along one axis, along with its estimated cost.
+    fn sah_axis_split(
+        &self,
+        bounds: AABB<Space>,
+        area: f64,
+        axis: Axis,
+        bins: usize,
+    ) -> Option<(f64, (Axis, f64))> {
+        let lo = axis.of(bounds.min);
+        let extent = axis.of(bounds.max) - lo;
+        if extent <= 0.0 {
+            return None;
+        }
+
+        // Count how many shapes start and end in each bin. A shape lies to the left of a plane if
+        // it starts before it, and to the right if it ends after it.
+        let bin_of = |x: f64| (((x - lo) / extent * bins as f64) as usize).min(bins - 1);
+        let mut starts = vec![0usize; bins];
+        let mut ends = vec![0usize; bins];
+        for shape in &self.shapes {
+            starts[bin_of(axis.of(shape.aabb.min))] += 1;
+            ends[bin_of(axis.of(shape.aabb.max))] += 1;
+        }
+
+        let mut best: Option<(f64, (Axis, f64))> = None;
+        let mut left = 0;
+        let mut right: usize = ends.iter().sum();
+        for plane in 1..bins {
+            left += starts[plane - 1];
+            right -= ends[plane - 1];
+
+            let point = lo + extent * plane as f64 / bins as f64;
+            let (left_bounds, right_bounds) = split_bounds(bounds, axis, point);
+            let cost = TRAVERSAL_COST
+                + INTERSECTION_COST
+                    * (surface_area(left_bounds) * left as f64
+                        + surface_area(right_bounds) * right as f64)
+                    / area;
+            if best.is_none_or(|(best_cost, _)| cost < best_cost) {
+                best = Some((cost, (axis, point)));
+            }
         }
-        if best_axis.is_none() {
+        best
+    }
+}
+
+impl<Space> Node<Space>
+where
+    Space: Copy + Send + Sync + Sized + std::fmt::Debug + 'static,
+{
+    fn new(
+        shapes: Vec<Arc<BoundedShape<Space>>>,
+        bounds: AABB<Space>,
+        strategy: BVHStrategy,
+        level: usize,
+    ) -> (Self, usize) {
+        let mut node = Self::Leaf(LeafNode { shapes });
+        let depth = node.split(bounds, strategy, level);
+        (node, depth + 1)
+    }
+
+    fn split(&mut self, bounds: AABB<Space>, strategy: BVHStrategy, level: usize) -> usize {
+        let leaf = match self {
+            Self::Parent(_) => return 0,
+            Self::Leaf(leaf) => leaf,
+        };
+        if leaf.shapes.len() < 8 || level >= MAX_DEPTH {
             return 1;
         }
-        let (l, r) = leaf.partition(best, best_axis.unwrap(), best_point);
-        let (left, depth) = Node::new(l);
-        let (right, _) = Node::new(r);
+
+        let best_split = match strategy {
+            BVHStrategy::Median => leaf.median_split(),
+            BVHStrategy::Sah { bins } => leaf.sah_split(bounds, bins),
+        };
+        let (axis, point) = match best_split {
+            Some(split) => split,
+            None => return 1,
+        };
+
+        let (l, r) = leaf.partition(axis, point);
+        let (left_bounds, right_bounds) = split_bounds(bounds, axis, point);
+        let build_left = || Node::new(l, left_bounds, strategy, level + 1);
+        let build_right = || Node::new(r, right_bounds, strategy, level + 1);
+        let ((left, left_depth), (right, right_depth)) =
+            if leaf.shapes.len() >= PARALLEL_BUILD_THRESHOLD {
+                rayon::join(build_left, build_right)
+            } else {
+                (build_left(), build_right())
+            };
         *self = Self::Parent(ParentNode {
-            axis: best_axis.unwrap(),
-            point: best_point,
+            axis,
+            point,
             left: Box::new(left),
             right: Box::new(right),
         });
-        depth
+        left_depth.max(right_depth)
     }
-}
 
-impl Node<WorldSpace> {
-    fn intersects(&self, ray: Ray, tmin: f64, tmax: f64) -> Option<HitData> {
+    /// Appends this node and its children to `nodes` in depth-first order, and the shapes of its
+    /// leaves to `leaf_shapes`.
+    fn flatten(self, nodes: &mut Vec<FlatNode>, leaf_shapes: &mut Vec<BoundedShape<Space>>) {
         match self {
-            Self::Parent(parent_node) => parent_node.intersects(ray, tmin, tmax),
-            Self::Leaf(leaf_node) => leaf_node.intersects(ray),
+            Self::Leaf(leaf) => {
+                nodes.push(FlatNode::Leaf {
+                    start: leaf_shapes.len() as u32,
+                    count: leaf.shapes.len() as u32,
+                });
+                leaf_shapes.extend(leaf.shapes.iter().map(|shape| BoundedShape::clone(shape)));
+            }
+            Self::Parent(parent) => {
+                let ndx = nodes.len();
+                nodes.push(FlatNode::Parent {
+                    axis: parent.axis,
+                    point: parent.point,
+                    right: 0,
+                });
+                parent.left.flatten(nodes, leaf_shapes);
+                let right_ndx = nodes.len() as u32;
+                if let FlatNode::Parent { right, .. } = &mut nodes[ndx] {
+                    *right = right_ndx;
+                }
+                parent.right.flatten(nodes, leaf_shapes);
+            }
         }
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 struct BoundedShape<Space>
 where
     Space: Copy + Send + Sync + Sized + std::fmt::Debug + 'static,
 {
+    id: ShapeId,
     shape: Arc<dyn Shape<Space>>,
     aabb: AABB<Space>,
 }
 
+/// Checks that a shape isn't degenerate, and finds its bounding box.
+pub(crate) fn check_shape<Space>(
+    id: ShapeId,
+    shape: &dyn Shape<Space>,
+) -> Result<Option<AABB<Space>>, DegenerateShape>
+where
+    Space: Copy + Send + Sync + Sized + std::fmt::Debug + 'static,
+{
+    let aabb = shape.bounding_box();
+    if let Some(aabb) = aabb {
+        let coords = [
+            aabb.min.x, aabb.min.y, aabb.min.z, aabb.max.x, aabb.max.y, aabb.max.z,
+        ];
+        if !coords.iter().all(|coord| coord.is_finite()) {
+            let reason = "its bounding box isn't finite".to_string();
+            return Err(DegenerateShape::new(id, shape, reason));
+        }
+        if aabb.min.x > aabb.max.x || aabb.min.y > aabb.max.y || aabb.min.z > aabb.max.z {
+            let reason = "its bounding box is inside out".to_string();
+            return Err(DegenerateShape::new(id, shape, reason));
+        }
+    }
+    shape
+        .validate()
+        .map_err(|reason| DegenerateShape::new(id, shape, reason))?;
+    Ok(aabb)
+}
+
+/// Finds the bounding box of a shape, or leaves it out with a warning if it's degenerate.
+fn store_shape<Space>(id: ShapeId, shape: &Arc<dyn Shape<Space>>) -> Option<StoredShape<Space>>
+where
+    Space: Copy + Send + Sync + Sized + std::fmt::Debug + 'static,
+{
+    match check_shape(id, shape.as_ref()) {
+        Ok(aabb) => Some((Arc::clone(shape), aabb)),
+        Err(err) => {
+            warn!("Leaving out a shape: {}", err);
+            None
+        }
+    }
+}
+
+/// Finds the bounding boxes of the given shapes, whose ids start at `first_id`.
+fn stored_shapes<Space>(
+    first_id: usize,
+    shapes: &[Arc<dyn Shape<Space>>],
+) -> Vec<Option<StoredShape<Space>>>
+where
+    Space: Copy + Send + Sync + Sized + std::fmt::Debug + 'static,
+{
+    shapes
+        .par_iter()
+        .enumerate()
+        .map(|(ndx, shape)| store_shape(ShapeId(first_id + ndx), shape))
+        .collect()
+}
+
 fn bounding_box_for_shapes<Space>(shapes: &[Arc<BoundedShape<Space>>]) -> AABB<Space>
 where
     Space: Copy + Send + Sync + Sized + std::fmt::Debug + 'static,
@@ -340,9 +1098,93 @@ where
     }
 }
 
-fn bounding_box_intersects(aabb: AABB<WorldSpace>, ray: Ray) -> (f64, f64) {
-    let v1 = (aabb.min - ray.point).component_div(ray.dir);
-    let v2 = (aabb.max - ray.point).component_div(ray.dir);
+/// Whether two boxes overlap or touch.
+fn boxes_overlap<Space>(a: AABB<Space>, b: AABB<Space>) -> bool
+where
+    Space: Copy + Send + Sync + Sized + std::fmt::Debug + 'static,
+{
+    a.min.x <= b.max.x
+        && a.max.x >= b.min.x
+        && a.min.y <= b.max.y
+        && a.max.y >= b.min.y
+        && a.min.z <= b.max.z
+        && a.max.z >= b.min.z
+}
+
+/// Cuts a bounding box in two at the given point along an axis.
+fn split_bounds<Space>(bounds: AABB<Space>, axis: Axis, point: f64) -> (AABB<Space>, AABB<Space>)
+where
+    Space: Copy + Send + Sync + Sized + std::fmt::Debug + 'static,
+{
+    let (mut left, mut right) = (bounds, bounds);
+    match axis {
+        Axis::X => {
+            left.max.x = point;
+            right.min.x = point;
+        }
+        Axis::Y => {
+            left.max.y = point;
+            right.min.y = point;
+        }
+        Axis::Z => {
+            left.max.z = point;
+            right.min.z = point;
+        }
+    }
+    (left, right)
+}
+
+/// The twelve edges of a box.
+fn box_edges<Space>(aabb: AABB<Space>, tag: usize) -> Vec<LineSegment<Space>>
+where
+    Space: Copy + Send + Sync + Sized + std::fmt::Debug + 'static,
+{
+    let (min, max) = (aabb.min, aabb.max);
+    let corner = |x: bool, y: bool, z: bool| {
+        Point3D::new(
+            if x { max.x } else { min.x },
+            if y { max.y } else { min.y },
+            if z { max.z } else { min.z },
+        )
+    };
+    let mut edges = Vec::with_capacity(12);
+    for a in [false, true] {
+        for b in [false, true] {
+            edges.push(LineSegment::tagged(
+                corner(false, a, b),
+                corner(true, a, b),
+                tag,
+            ));
+            edges.push(LineSegment::tagged(
+                corner(a, false, b),
+                corner(a, true, b),
+                tag,
+            ));
+            edges.push(LineSegment::tagged(
+                corner(a, b, false),
+                corner(a, b, true),
+                tag,
+            ));
+        }
+    }
+    edges
+}
+
+fn surface_area<Space>(aabb: AABB<Space>) -> f64
+where
+    Space: Copy + Send + Sync + Sized + std::fmt::Debug + 'static,
+{
+    let size = aabb.max - aabb.min;
+    2.0 * (size.x * size.y + size.y * size.z + size.z * size.x)
+}
+
+fn bounding_box_intersects<Space>(aabb: AABB<Space>, ray: Ray, inv_dir: [f64; 3]) -> (f64, f64)
+where
+    Space: Copy + Send + Sync + Sized + std::fmt::Debug + 'static,
+{
+    let inv_dir = WVec3::from(inv_dir);
+    let v1 = (aabb.min.cast_unit() - ray.point).component_mul(inv_dir);
+    let v2 = (aabb.max.cast_unit() - ray.point).component_mul(inv_dir);
 
     let ov1 = v1.min(v2);
     let ov2 = v1.max(v2);
@@ -364,3 +1206,321 @@ fn median(nums: &[f64]) -> f64 {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::shapes::{Plane, RectPrism};
+    use crate::{WPoint3, WVec3};
+
+    /// A large ground box with a grid of small boxes standing on it.
+    fn uneven_scene() -> Vec<Arc<dyn Shape<WorldSpace>>> {
+        let mut shapes: Vec<Arc<dyn Shape<WorldSpace>>> = vec![Arc::new(RectPrism::new(
+            WVec3::new(-100.0, -1.0, -100.0),
+            WVec3::new(100.0, 0.0, 100.0),
+        ))];
+        for x in 0..20 {
+            for z in 0..20 {
+                let min = WVec3::new(x as f64 * 2.0, 0.0, z as f64 * 2.0);
+                shapes.push(Arc::new(RectPrism::new(min, min + WVec3::splat(0.5))));
+            }
+        }
+        shapes
+    }
+
+    fn largest_leaf(tree: &BVHTree<WorldSpace>) -> usize {
+        tree.nodes
+            .iter()
+            .filter_map(|node| match node {
+                FlatNode::Leaf { count, .. } => Some(*count as usize),
+                FlatNode::Parent { .. } => None,
+            })
+            .max()
+            .unwrap_or(0)
+    }
+
+    #[test]
+    fn test_strategies_agree() {
+        let shapes = uneven_scene();
+        let median = BVHTree::with_strategy(&shapes, BVHStrategy::Median);
+        let sah = BVHTree::with_strategy(&shapes, BVHStrategy::Sah { bins: 16 });
+
+        for i in 0..200 {
+            let target = WPoint3::new((i % 20) as f64 * 2.1, 0.3, (i / 10) as f64 * 1.9);
+            let eye = WPoint3::new(-10.0 + i as f64 * 0.1, 15.0, -20.0);
+            let ray = Ray::normalize_new(eye, target - eye);
+
+            let expected = shapes
+                .iter()
+                .filter_map(|shape| shape.hit_by(&ray))
+                .map(|hit| hit.dist_to)
+                .fold(f64::INFINITY, f64::min);
+            for tree in [&median, &sah] {
+                let found = tree.closest_hit(ray).map(|hit| hit.dist_to);
+                assert_eq!(found, Some(expected));
+            }
+        }
+    }
+
+    #[test]
+    fn test_ray_queries() {
+        let mut shapes = uneven_scene();
+        shapes.push(Arc::new(Plane::new(
+            WPoint3::new(0.0, 0.0, 60.0),
+            WVec3::new(0.0, 0.0, -1.0),
+        )));
+        let tree = BVHTree::new(&shapes);
+
+        // along a row of small boxes, then into the plane
+        let ray = Ray::new(WPoint3::new(0.25, 0.25, -5.0), WVec3::new(0.0, 0.0, 1.0));
+        let hits = tree.all_hits(ray);
+        let ids: Vec<_> = hits.iter().map(|hit| hit.shape_id.unwrap().0).collect();
+        assert_eq!(ids, (1..=20).chain([401]).collect::<Vec<_>>());
+        assert!(hits
+            .windows(2)
+            .all(|pair| pair[0].dist_to <= pair[1].dist_to));
+        assert_eq!(
+            tree.closest_hit(ray).map(|hit| hit.shape_id),
+            Some(hits[0].shape_id)
+        );
+        assert!(tree.any_hit(ray).is_some());
+
+        // ends before reaching the first box
+        let short = Ray::bounded(ray.point, ray.dir, 0.0, 4.0);
+        assert!(tree.all_hits(short).is_empty());
+        assert!(tree.any_hit(short).is_none());
+    }
+
+    #[derive(Debug, Clone, Copy)]
+    struct LocalSpace;
+
+    /// A box in a space other than world space, such as that of a prototype.
+    #[derive(Debug)]
+    struct LocalBox(RectPrism);
+
+    impl Shape<LocalSpace> for LocalBox {
+        fn hit_by(&self, ray: &Ray) -> Option<HitData> {
+            self.0.hit_by(ray)
+        }
+
+        fn paths(&self) -> Vec<LineSegment<LocalSpace>> {
+            Vec::new()
+        }
+
+        fn bounding_box(&self) -> Option<AABB<LocalSpace>> {
+            self.0.bounding_box().map(|aabb| aabb.cast_unit())
+        }
+    }
+
+    #[test]
+    fn test_ray_queries_in_other_spaces() {
+        let shapes: Vec<Arc<dyn Shape<LocalSpace>>> = (0..4)
+            .map(|i| {
+                let min = WVec3::new(0.0, 0.0, i as f64 * 2.0);
+                let prism = RectPrism::new(min, min + WVec3::splat(1.0));
+                Arc::new(LocalBox(prism)) as _
+            })
+            .collect();
+        let tree = BVHTree::new(&shapes);
+
+        let ray = Ray::new(WPoint3::new(0.5, 0.5, -5.0), WVec3::new(0.0, 0.0, 1.0));
+        let ids: Vec<_> = tree.all_hits(ray).iter().map(|hit| hit.shape_id).collect();
+        assert_eq!(ids, (0..4).map(|id| Some(ShapeId(id))).collect::<Vec<_>>());
+        assert_eq!(tree.closest_hit(ray).map(|hit| hit.dist_to), Some(5.0));
+        assert!(tree.any_hit(ray).is_some());
+    }
+
+    #[test]
+    fn test_overlapping() {
+        let shapes = uneven_scene();
+        let tree = BVHTree::new(&shapes);
+
+        for (min, max) in [
+            (WPoint3::new(1.0, 0.1, 1.0), WPoint3::new(5.0, 0.2, 3.0)),
+            (WPoint3::new(-3.0, 0.4, 10.2), WPoint3::new(40.0, 2.0, 10.3)),
+            (WPoint3::new(2.5, 0.5, 2.5), WPoint3::new(2.5, 0.5, 2.5)),
+            (WPoint3::new(0.0, 5.0, 0.0), WPoint3::new(10.0, 6.0, 10.0)),
+        ] {
+            let aabb = AABB::new(min, max);
+            let expected: Vec<_> = shapes
+                .iter()
+                .enumerate()
+                .filter(|(_, shape)| boxes_overlap(shape.bounding_box().unwrap(), aabb))
+                .map(|(ndx, _)| ShapeId(ndx))
+                .collect();
+            assert_eq!(tree.overlapping(aabb), expected);
+        }
+    }
+
+    #[test]
+    fn test_in_frustum() {
+        let shapes = uneven_scene();
+        let tree = BVHTree::new(&shapes);
+        let camera = Camera::look_at(
+            WPoint3::new(10.0, 4.0, -5.0),
+            WVec3::new(20.0, 0.0, 20.0),
+            WVec3::new(0.0, 1.0, 0.0),
+        )
+        .perspective(30.0, 400.0, 300.0, 0.1, 30.0);
+
+        let expected: Vec<_> = shapes
+            .iter()
+            .enumerate()
+            .filter(|(_, shape)| camera.overlaps_frustum(shape.bounding_box().unwrap()))
+            .map(|(ndx, _)| ShapeId(ndx))
+            .collect();
+        let found = tree.in_frustum(&camera);
+        assert_eq!(found, expected);
+        // the ground and some, but not all, of the small boxes
+        assert!(found.len() > 10 && found.len() < shapes.len());
+    }
+
+    /// Checks the tree against testing every one of the given shapes, which are listed by id.
+    fn assert_matches(tree: &BVHTree<WorldSpace>, shapes: &[Option<Arc<dyn Shape<WorldSpace>>>]) {
+        for i in 0..100 {
+            let target = WPoint3::new((i % 20) as f64 * 2.6 - 5.0, 0.3, (i / 5) as f64 * 2.3 - 5.0);
+            let eye = WPoint3::new(-10.0 + i as f64 * 0.3, 15.0, -20.0);
+            let ray = Ray::normalize_new(eye, target - eye);
+
+            let expected = shapes
+                .iter()
+                .flatten()
+                .filter_map(|shape| shape.hit_by(&ray))
+                .map(|hit| hit.dist_to)
+                .fold(None, |a: Option<f64>, b| Some(a.map_or(b, |a| a.min(b))));
+            assert_eq!(tree.closest_hit(ray).map(|hit| hit.dist_to), expected);
+        }
+
+        let aabb = AABB::new(WPoint3::new(-5.0, 0.1, -5.0), WPoint3::new(50.0, 0.2, 8.0));
+        let expected: Vec<_> = shapes
+            .iter()
+            .enumerate()
+            .filter(|(_, shape)| {
+                // shapes without a bounding box overlap everything
+                shape.as_ref().is_some_and(|shape| {
+                    shape
+                        .bounding_box()
+                        .is_none_or(|bounds| boxes_overlap(bounds, aabb))
+                })
+            })
+            .map(|(ndx, _)| ShapeId(ndx))
+            .collect();
+        assert_eq!(tree.overlapping(aabb), expected);
+    }
+
+    #[test]
+    fn test_rebucket() {
+        let mut shapes: Vec<_> = uneven_scene().into_iter().map(Some).collect();
+        let mut tree = BVHTree::new(&shapes.iter().flatten().cloned().collect::<Vec<_>>());
+        let built_cost = tree.built_cost;
+
+        // nudge every tenth box within its cell, and move one outside of the tree
+        let mut moved = Vec::new();
+        for ndx in (1..shapes.len()).step_by(10) {
+            let min = WVec3::new(
+                ((ndx - 1) / 20) as f64 * 2.0 + 0.3,
+                0.0,
+                ((ndx - 1) % 20) as f64 * 2.0 + 0.4,
+            );
+            let shape: Arc<dyn Shape<WorldSpace>> =
+                Arc::new(RectPrism::new(min, min + WVec3::splat(0.5)));
+            moved.push((ShapeId(ndx), shape));
+        }
+        moved.push((
+            ShapeId(2),
+            Arc::new(RectPrism::new(
+                WVec3::new(-3.0, 0.0, -3.0),
+                WVec3::new(-2.0, 0.15, -2.0),
+            )),
+        ));
+        for (id, shape) in &moved {
+            shapes[id.0] = Some(Arc::clone(shape));
+        }
+        assert!(tree.rebucket(&moved).is_empty());
+        assert_eq!(tree.built_cost, built_cost);
+        assert_matches(&tree, &shapes);
+
+        let removed = [ShapeId(5), ShapeId(77), ShapeId(300)];
+        for id in removed {
+            shapes[id.0] = None;
+        }
+        tree.remove(&removed);
+        assert!(tree.shape(ShapeId(5)).is_none());
+
+        let inserted: Vec<Arc<dyn Shape<WorldSpace>>> = vec![
+            Arc::new(RectPrism::new(
+                WVec3::new(4.2, 0.0, 4.2),
+                WVec3::new(4.4, 0.18, 4.4),
+            )),
+            Arc::new(Plane::new(
+                WPoint3::new(0.0, 0.0, 45.0),
+                WVec3::new(0.0, 0.0, -1.0),
+            )),
+        ];
+        assert_eq!(tree.insert(&inserted), vec![ShapeId(401), ShapeId(402)]);
+        shapes.extend(inserted.into_iter().map(Some));
+        assert_eq!(tree.built_cost, built_cost);
+        assert_matches(&tree, &shapes);
+
+        // piling every box into the same spot, far away from the grid, makes the old splits useless
+        let moved: Vec<_> = (1..=400)
+            .filter(|ndx| shapes[*ndx].is_some())
+            .map(|ndx| {
+                let shape: Arc<dyn Shape<WorldSpace>> = Arc::new(RectPrism::new(
+                    WVec3::new(-60.0 + ndx as f64 * 0.01, 0.0, -60.0),
+                    WVec3::new(-59.5 + ndx as f64 * 0.01, 0.5, -59.5),
+                ));
+                (ShapeId(ndx), shape)
+            })
+            .collect();
+        for (id, shape) in &moved {
+            shapes[id.0] = Some(Arc::clone(shape));
+        }
+        assert!(tree.rebucket(&moved).is_empty());
+        assert_ne!(tree.built_cost, built_cost);
+        assert_matches(&tree, &shapes);
+    }
+
+    #[test]
+    fn test_sah_leaf_size() {
+        let tree = BVHTree::with_strategy(&uneven_scene(), BVHStrategy::Sah { bins: 16 });
+        assert!(largest_leaf(&tree) <= 8);
+    }
+
+    #[test]
+    fn test_stats() {
+        let mut shapes = uneven_scene();
+        shapes.push(Arc::new(Plane::new(
+            WPoint3::new(0.0, 0.0, 60.0),
+            WVec3::new(0.0, 0.0, -1.0),
+        )));
+        let tree = BVHTree::new(&shapes);
+        let stats = tree.stats();
+
+        assert_eq!(stats.nodes, tree.nodes.len());
+        assert_eq!(stats.leaves() * 2 - 1, stats.nodes);
+        assert_eq!(stats.leaf_sizes.len() - 1, largest_leaf(&tree));
+        assert!(stats.depth > 0 && stats.depth <= MAX_DEPTH);
+        assert_eq!(stats.unbounded, 1);
+        assert_eq!(stats.sah_cost, tree.built_cost);
+
+        // the ground box lies in every leaf
+        let refs: usize = (stats.leaf_sizes.iter().enumerate())
+            .map(|(size, leaves)| size * leaves)
+            .sum();
+        assert_eq!(refs, 401 + stats.duplicate_refs);
+        assert!(stats.duplicate_refs >= stats.leaves() - 1);
+
+        let root = tree.debug_paths(0..1);
+        assert_eq!(root.len(), 12);
+        assert!(root.iter().all(|path| path.tag == 0));
+        let corners = root.iter().flat_map(|path| [path.p1, path.p2]);
+        assert_eq!(AABB::from_points(corners), tree.aabb);
+
+        assert_eq!(tree.debug_paths(..).len(), 12 * stats.nodes);
+        let deepest = tree.debug_paths(stats.depth..);
+        assert!(!deepest.is_empty());
+        assert!(deepest.iter().all(|path| path.tag == stats.depth));
+        assert!(tree.debug_paths(stats.depth + 1..).is_empty());
+    }
+}
@@ -1,4 +1,4 @@
-pub(crate) mod bvh;
+pub mod bvh;
 pub mod path;
 pub mod ray;
 pub mod scene;
@@ -6,10 +6,11 @@ pub mod shapes;
 
 use euclid::*;
 
+pub use bvh::{BVHStats, BVHStrategy, BVHTree, DegenerateShape};
 use path::LineSegment;
-pub use ray::{HitData, Ray};
+pub use ray::{HitData, Ray, ShapeId};
 
-pub use scene::{Camera, Scene};
+pub use scene::{Axonometry, Camera, Projection, Scene};
 
 #[cfg(test)]
 pub(crate) static EPSILON: f64 = 0.004;
@@ -40,5 +41,16 @@ where
 {
     fn hit_by(&self, ray: &Ray) -> Option<HitData>;
     fn paths(&self) -> Vec<LineSegment<Space>>;
+    /// Paths which depend on where the shape is viewed from, such as the silhouettes of curved
+    /// surfaces.
+    fn silhouette_paths(&self, _eye: Point3D<f64, Space>) -> Vec<LineSegment<Space>> {
+        Vec::new()
+    }
     fn bounding_box(&self) -> Option<AABB<Space>>;
+    /// Checks that the shape is well formed, describing what's wrong with it otherwise.
+    /// Degenerate shapes, such as triangles whose corners lie on a single line, can't be hit
+    /// reliably, so they are left out of scenes.
+    fn validate(&self) -> Result<(), String> {
+        Ok(())
+    }
 }
@@ -35,6 +35,43 @@ where
         let p2t = transformation.transform_point3d(p2);
         p1t.and_then(|p1| p2t.map(|p2| LineSegment::tagged(p1, p2, self.tag)))
     }
+
+    /// The part of the segment between the given fractions of the way from `p1` to `p2`.
+    pub fn slice(&self, t1: f64, t2: f64) -> LineSegment<Space> {
+        LineSegment::tagged(
+            self.p1.lerp(self.p2, t1),
+            self.p1.lerp(self.p2, t2),
+            self.tag,
+        )
+    }
+}
+
+/// Finds the fractions of the way along a segment between which it lies inside of a convex
+/// region, given the signed distances of both of its ends to each of the region's boundaries,
+/// which are positive on the inside. Returns `None` if no part of the segment lies inside, or if
+/// it only touches the region.
+///
+/// Distances only need to vary linearly along the segment, so this also clips segments in
+/// homogeneous coordinates, against the planes where a coordinate equals `w` or `-w`.
+pub fn clip_range<I>(distances: I) -> Option<(f64, f64)>
+where
+    I: IntoIterator<Item = (f64, f64)>,
+{
+    let (mut t1, mut t2) = (0.0, 1.0);
+    for (d1, d2) in distances {
+        if d1 < 0.0 && d2 < 0.0 {
+            return None;
+        } else if d1 < 0.0 {
+            t1 = f64::max(t1, d1 / (d1 - d2));
+        } else if d2 < 0.0 {
+            t2 = f64::min(t2, d1 / (d1 - d2));
+        }
+    }
+    if t1 < t2 {
+        Some((t1, t2))
+    } else {
+        None
+    }
 }
 
 pub fn simplify_segments<T>(paths: &[LineSegment<T>], threshold: f64) -> Vec<LineSegment<T>>
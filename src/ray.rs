@@ -1,4 +1,4 @@
-use crate::{WPoint3, WVec3};
+use crate::{Shape, WPoint3, WVec3};
 
 #[cfg(test)]
 use euclid::approxeq::ApproxEq as EuclidApproxEq;
@@ -7,26 +7,97 @@ use float_cmp::{approx_eq, ApproxEq};
 
 #[derive(Debug, Copy, Clone)]
 #[cfg_attr(test, derive(PartialEq))]
-/// A ray: a point in space and a direction towards which the ray continues infintely.
+/// A ray: a point in space and a direction towards which the ray continues. Only the part of the
+/// ray between `tmin` and `tmax` is considered when looking for intersections, which by default
+/// is the whole ray.
 pub struct Ray {
     /// The point in space from which the ray originates.
     pub point: WPoint3,
     /// The direction towards which the ray extends. This vector *must* be normalized.
     pub dir: WVec3,
+    /// The distance along the ray at which it starts.
+    pub tmin: f64,
+    /// The distance along the ray at which it ends.
+    pub tmax: f64,
 }
 
 impl Ray {
     pub fn new(point: WPoint3, dir: WVec3) -> Ray {
-        #[cfg(test)]
-        assert!(approx_eq!(f64, dir.length(), 1.0, epsilon = 0.002));
-        Ray { point, dir }
+        Ray::bounded(point, dir, 0.0, f64::INFINITY)
     }
 
     pub fn normalize_new(point: WPoint3, dir: WVec3) -> Ray {
         Ray::new(point, dir.normalize())
     }
+
+    /// Creates a ray which only extends from `tmin` to `tmax` along `dir`.
+    pub fn bounded(point: WPoint3, dir: WVec3, tmin: f64, tmax: f64) -> Ray {
+        #[cfg(test)]
+        assert!(approx_eq!(f64, dir.length(), 1.0, epsilon = 0.002));
+        Ray {
+            point,
+            dir,
+            tmin,
+            tmax,
+        }
+    }
+
+    /// Creates a ray which starts at `from` and ends at `to`.
+    pub fn between(from: WPoint3, to: WPoint3) -> Ray {
+        let v = to - from;
+        let length = v.length();
+        Ray::bounded(from, v / length, 0.0, length)
+    }
+
+    /// The point at the given distance along the ray.
+    pub fn at(&self, t: f64) -> WPoint3 {
+        self.point + self.dir * t
+    }
+
+    /// Whether the ray points anywhere: its direction is finite, and not zero. Rays without a
+    /// direction miss everything.
+    pub fn has_direction(&self) -> bool {
+        let dir = self.dir.to_array();
+        dir.iter().all(|d| d.is_finite()) && dir.iter().any(|&d| d != 0.0)
+    }
+
+    /// Whether the given distance along the ray lies between `tmin` and `tmax`.
+    pub fn contains(&self, t: f64) -> bool {
+        t >= self.tmin && t <= self.tmax
+    }
+
+    /// Finds where `shape` is first struck by the bounded part of this ray.
+    ///
+    /// Shapes themselves look for hits along the entire length of a ray, so the shape is tested
+    /// with a ray that starts at `tmin` instead, which keeps it from reporting hits that lie
+    /// before the start of this one.
+    pub fn hit<Space, S>(&self, shape: &S) -> Option<HitData>
+    where
+        Space: Copy + Send + Sync + Sized + std::fmt::Debug,
+        S: Shape<Space> + ?Sized,
+    {
+        if !self.has_direction() {
+            return None;
+        }
+        let start = Ray {
+            point: self.at(self.tmin),
+            tmin: 0.0,
+            tmax: f64::INFINITY,
+            ..*self
+        };
+        let mut hit = shape.hit_by(&start)?;
+        hit.dist_to += self.tmin;
+        // Degenerate shapes and rays may lead to hits at a distance of NaN, which fail this
+        // comparison, and so are misses.
+        (hit.dist_to <= self.tmax).then_some(hit)
+    }
 }
 
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+/// Identifies a shape within the collection that it was added to, e.g. by its position in the list
+/// of geometry given to `Scene::new`.
+pub struct ShapeId(pub usize);
+
 #[derive(Debug, Copy, Clone)]
 /// A structure describing the shape and location that a ray has struck.
 pub struct HitData {
@@ -34,6 +105,16 @@ pub struct HitData {
     pub hit_point: WPoint3,
     /// The distance that a ray travelled to hit this shape.
     pub dist_to: f64,
+    /// The normalized outward-facing surface normal at the hit point. This is a zero vector for
+    /// shapes which do not provide one.
+    pub normal: WVec3,
+    /// The shape which was hit. Shapes leave this empty; it is filled in by the structure which
+    /// holds them, such as a `Scene`.
+    pub shape_id: Option<ShapeId>,
+    /// The tag of the shape which was hit.
+    pub tag: usize,
+    /// The surface parameters of the hit point, for shapes which have them.
+    pub uv: Option<(f64, f64)>,
 }
 
 impl HitData {
@@ -41,8 +122,32 @@ impl HitData {
         HitData {
             hit_point,
             dist_to,
+            normal: WVec3::zero(),
+            shape_id: None,
+            tag: 0,
+            uv: None,
         }
     }
+
+    pub fn with_normal(mut self, normal: WVec3) -> HitData {
+        self.normal = normal;
+        self
+    }
+
+    pub fn with_tag(mut self, tag: usize) -> HitData {
+        self.tag = tag;
+        self
+    }
+
+    pub fn with_uv(mut self, u: f64, v: f64) -> HitData {
+        self.uv = Some((u, v));
+        self
+    }
+
+    pub fn with_shape_id(mut self, shape_id: ShapeId) -> HitData {
+        self.shape_id = Some(shape_id);
+        self
+    }
 }
 
 #[cfg(test)]
@@ -61,7 +166,6 @@ impl ApproxEq for &HitData {
 
     fn approx_eq<M: Into<Self::Margin>>(self, other: Self, margin: M) -> bool {
         let margin = margin.into();
-        self.hit_point.approx_eq(&other.hit_point)
-            && self.dist_to.approx_eq(other.dist_to, margin)
+        self.hit_point.approx_eq(&other.hit_point) && self.dist_to.approx_eq(other.dist_to, margin)
     }
 }
@@ -1,11 +1,174 @@
-use bvh::BVHTree;
-use path::simplify_segments;
+use path::{clip_range, simplify_segments};
 use rayon::prelude::*;
 use std::sync::Arc;
 use tracing::info;
 
 use crate::*;
 
+/// How far visibility rays start from the point they are cast from, relative to the size of the
+/// box around the scene's bounded shapes, or to the length of the ray if there are none.
+const VISIBILITY_OFFSET: f64 = 1.0e-6;
+
+/// How far behind the camera, relative to its far plane, the eye of a parallel projection is
+/// pretended to be when finding the silhouettes of shapes. Shapes only know how to find their
+/// silhouettes as seen from a point, and from this far away, lines of sight are parallel for all
+/// practical purposes.
+const PARALLEL_EYE_DISTANCE: f64 = 1.0e6;
+
+/// How far, in pixels, the projection of a segment may stray from a straight line between the
+/// projections of its ends, before it's split up to follow the curve it projects to.
+const CURVE_TOLERANCE: f64 = 0.25;
+/// The widest angle, in degrees, which a segment may span as seen from the eye before it's split
+/// up, however straight its projection looks. Only checking how far the middle of a segment
+/// strays would miss curves which wind back and forth.
+const CURVE_MAX_ANGLE: f64 = 10.0;
+/// How many times a segment may be split in half to follow its curve. Segments which still
+/// don't follow it then cross a discontinuity, such as the seam of a panorama, and are dropped.
+const CURVE_MAX_DEPTH: usize = 16;
+
+/// How long, in pixels, the chunks which segments are chopped into for visibility testing are on
+/// the canvas, unless set with `Camera::with_chunk_size`.
+const DEFAULT_CHUNK_SIZE: f64 = 1.0;
+/// How many times the stretch between two chunks may be split in half while looking for where
+/// visibility changes along it. By then, it's far shorter than any sensible tolerance.
+const BOUNDARY_MAX_DEPTH: usize = 48;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// How a camera projects the world onto its canvas.
+pub enum Projection {
+    /// Lines of sight meet at the eye, so that things further away appear smaller. `fovy` is the
+    /// vertical field of view, in degrees.
+    ///
+    /// The lens may be shifted sideways and up by `shift_x` and `shift_y`, in fractions of the
+    /// width and height of the view, which moves what's in view without turning the camera. This
+    /// keeps lines which are parallel to the canvas parallel, such as the verticals of buildings
+    /// seen by a level camera.
+    Perspective {
+        fovy: f64,
+        shift_x: f64,
+        shift_y: f64,
+    },
+    /// Lines of sight run parallel to the view direction, so that things appear the same size
+    /// however far away they are, as in technical drawings. The view covers a rectangle of
+    /// `view_width` by `view_height` world units around the view direction.
+    Orthographic { view_width: f64, view_height: f64 },
+    /// Lines of sight run parallel to each other, but slant away from the view direction. Things
+    /// in the plane through the point the camera is looking at keep their true shape, as in a
+    /// front view, while depth is drawn along lines which recede at `angle` degrees from the
+    /// canvas' horizontal, shortened by `scale`. The view covers `view_width` by `view_height`
+    /// world units of that plane.
+    Oblique {
+        view_width: f64,
+        view_height: f64,
+        angle: f64,
+        scale: f64,
+    },
+    /// An equidistant fisheye lens: how far a point is drawn from the middle of the canvas is
+    /// proportional to the angle between it and the view direction. `fov` is the angle across
+    /// the height of the canvas, in degrees, and may exceed 180 to see behind the camera.
+    Fisheye { fov: f64 },
+    /// A stereographic fisheye lens, which draws small shapes without distorting them, at the
+    /// cost of magnifying things towards the edges of the canvas. `fov` is the angle across the
+    /// height of the canvas, in degrees, and has to be less than 360.
+    Stereographic { fov: f64 },
+    /// A panorama whose canvas is a map of the directions around the eye: the angle around the
+    /// camera's vertical axis is drawn along the width of the canvas, and the angle above or
+    /// below the view direction along its height. A `fovx` of 360 and a `fovy` of 180 degrees
+    /// see everything.
+    Equirectangular { fovx: f64, fovy: f64 },
+}
+
+impl Projection {
+    /// Whether the projection keeps lines of sight parallel to each other, rather than letting
+    /// them meet at the eye.
+    pub fn is_parallel(&self) -> bool {
+        matches!(
+            self,
+            Projection::Orthographic { .. } | Projection::Oblique { .. }
+        )
+    }
+
+    /// Whether the projection bends straight lines into curves.
+    pub fn is_curvilinear(&self) -> bool {
+        matches!(
+            self,
+            Projection::Fisheye { .. }
+                | Projection::Stereographic { .. }
+                | Projection::Equirectangular { .. }
+        )
+    }
+
+    /// An oblique projection which draws depth at its full length.
+    pub fn cavalier(view_width: f64, view_height: f64, angle: f64) -> Projection {
+        Projection::Oblique {
+            view_width,
+            view_height,
+            angle,
+            scale: 1.0,
+        }
+    }
+
+    /// An oblique projection which draws depth at half its length, which looks more natural
+    /// than a cavalier projection.
+    pub fn cabinet(view_width: f64, view_height: f64, angle: f64) -> Projection {
+        Projection::Oblique {
+            view_width,
+            view_height,
+            angle,
+            scale: 0.5,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// A parallel view onto the world from a direction which shows three of its axes at once, given
+/// by how far it's rotated around the up direction and raised above the horizon, in degrees.
+///
+/// The rotation is measured from the world axis which is most perpendicular to the up
+/// direction, e.g. from the x axis when the z axis points up.
+pub enum Axonometry {
+    /// All three axes are shortened equally, and drawn 120 degrees apart.
+    Isometric,
+    /// The two horizontal axes are shortened equally, and the vertical one differently. An
+    /// elevation of about 30 degrees gives the 2:1 pixel slopes of many games.
+    Dimetric { elevation: f64 },
+    /// All three axes are shortened differently.
+    Trimetric { azimuth: f64, elevation: f64 },
+}
+
+impl Axonometry {
+    /// The rotation around the up direction and the elevation above the horizon, in degrees.
+    pub fn angles(&self) -> (f64, f64) {
+        match *self {
+            // Raised until the up direction is shortened as much as the horizontal axes are.
+            Axonometry::Isometric => (45.0, (1.0 / 2.0f64.sqrt()).atan().to_degrees()),
+            Axonometry::Dimetric { elevation } => (45.0, elevation),
+            Axonometry::Trimetric { azimuth, elevation } => (azimuth, elevation),
+        }
+    }
+
+    /// The normalized direction from the point being looked at towards the eye.
+    fn direction(&self, up: WVec3) -> WVec3 {
+        let up = up.normalize();
+        let axes = [
+            WVec3::new(1.0, 0.0, 0.0),
+            WVec3::new(0.0, 1.0, 0.0),
+            WVec3::new(0.0, 0.0, 1.0),
+        ];
+        let reference = axes
+            .iter()
+            .copied()
+            .min_by(|a, b| a.dot(up).abs().total_cmp(&b.dot(up).abs()))
+            .unwrap();
+        let u = (reference - up * reference.dot(up)).normalize();
+        let v = up.cross(u);
+
+        let (azimuth, elevation) = self.angles();
+        let (azimuth, elevation) = (azimuth.to_radians(), elevation.to_radians());
+        (u * azimuth.cos() + v * azimuth.sin()) * elevation.cos() + up * elevation.sin()
+    }
+}
+
 pub struct LookingCamera {
     eye: WPoint3,
     center: WVec3,
@@ -16,19 +179,27 @@ pub struct LookingCamera {
 #[derive(Debug, Copy, Clone)]
 pub struct Camera {
     eye: WPoint3,
-    _center: WVec3,
-    _up: WVec3,
+    center: WVec3,
+    up: WVec3,
 
-    _fovy: f64,
+    projection: Projection,
     width: f64,
     height: f64,
-    _aspect: f64,
+    aspect: f64,
     znear: f64,
     zfar: f64,
 
-    min_step_size: f64,
-    max_step_size: f64,
+    /// How long, in pixels, the chunks which segments are chopped into are on the canvas.
+    chunk_size: f64,
+    /// How close, in pixels, where visibility changes between neighboring chunks is found, if
+    /// it's looked for at all.
+    boundary_tolerance: Option<f64>,
 
+    /// The transformation from world space into the space of the camera, where it looks along
+    /// the negative z axis.
+    view: WCTransform,
+    /// The transformation from world space into clip space. Curvilinear projections can't be
+    /// written as a transformation, so for them, this only transforms into the camera's space.
     matrix: Transform3D<f64, WorldSpace, CanvasSpace>,
 }
 
@@ -64,55 +235,548 @@ impl Camera {
         }
     }
 
-    /// Chops a line segment into subsegments based on distance from camera
-    pub fn chop_segment(&self, segment: &LineSegment<WorldSpace>) -> Vec<LineSegment<WorldSpace>> {
-        // linearly interpolate step_size based on closest point to the camera
-        let p1 = segment.p1.to_vector();
-        let p2 = segment.p2.to_vector();
-        let segment_diff = p2 - p1;
-        let midpoint = p1 + (segment_diff / 2.0);
+    /// Looks at `center` from `distance` away, in the direction given by `axonometry`. This is
+    /// meant for parallel projections, such as `LookingCamera::orthographic`.
+    pub fn axonometric(
+        center: WVec3,
+        up: WVec3,
+        axonometry: Axonometry,
+        distance: f64,
+    ) -> LookingCamera {
+        let eye = center + axonometry.direction(up) * distance;
+        Camera::look_at(eye.to_point(), center, up)
+    }
+
+    pub fn eye(&self) -> WPoint3 {
+        self.eye
+    }
+
+    /// The point the camera is looking at.
+    pub fn center(&self) -> WVec3 {
+        self.center
+    }
+
+    pub fn up(&self) -> WVec3 {
+        self.up
+    }
+
+    pub fn projection(&self) -> Projection {
+        self.projection
+    }
+
+    /// The vertical field of view, in degrees, or `None` for parallel projections, which don't
+    /// have one.
+    pub fn fovy(&self) -> Option<f64> {
+        match self.projection {
+            Projection::Perspective { fovy, .. } | Projection::Equirectangular { fovy, .. } => {
+                Some(fovy)
+            }
+            Projection::Fisheye { fov } | Projection::Stereographic { fov } => Some(fov),
+            Projection::Orthographic { .. } | Projection::Oblique { .. } => None,
+        }
+    }
 
-        let eye = self.eye.to_vector();
+    /// The normalized direction in which the camera looks.
+    pub fn forward(&self) -> WVec3 {
+        (self.center - self.eye.to_vector()).normalize()
+    }
 
-        let t1 = (p1 - eye).length();
-        let t2 = (p2 - eye).length();
-        let t3 = (midpoint - eye).length();
+    /// The direction along lines of sight of parallel projections, from the world towards the
+    /// camera. It's scaled to move by one unit against the view direction.
+    fn backward(&self) -> WVec3 {
+        let forward = self.forward();
+        match self.projection {
+            Projection::Oblique { angle, scale, .. } => {
+                let right = forward.cross(self.up).normalize();
+                let up = right.cross(forward);
+                let angle = angle.to_radians();
+                (right * angle.cos() + up * angle.sin()) * scale - forward
+            }
+            _ => -forward,
+        }
+    }
 
-        let closest = f64::min(f64::min(t1, t2), t3);
+    pub fn aspect(&self) -> f64 {
+        self.aspect
+    }
 
-        let plane_dist = self.zfar - self.znear;
-        let scale = (closest - self.znear) / plane_dist;
+    pub fn width(&self) -> f64 {
+        self.width
+    }
 
-        let rough_step_size =
-            self.min_step_size + (scale * (self.max_step_size - self.min_step_size));
+    pub fn height(&self) -> f64 {
+        self.height
+    }
 
-        // Slice segment into equal-sized chunks of approx `rough_step_size` length
-        let segment_length = segment_diff.length();
-        if segment_length < rough_step_size {
-            return Vec::new();
+    /// The distance from the eye to the near clipping plane.
+    pub fn znear(&self) -> f64 {
+        self.znear
+    }
+
+    /// The distance from the eye to the far clipping plane.
+    pub fn zfar(&self) -> f64 {
+        self.zfar
+    }
+
+    /// How far away from the camera a point is. This is the distance to the eye, except for
+    /// parallel projections, for which it's the depth along the view direction.
+    pub fn distance_to(&self, point: WPoint3) -> f64 {
+        if self.projection.is_parallel() {
+            (point - self.eye).dot(self.forward())
+        } else {
+            (point - self.eye).length()
         }
+    }
 
-        let chunk_count = usize::max(
-            (segment_length / rough_step_size).round_ties_even() as usize,
-            1,
-        );
-        if chunk_count == 1 {
-            return vec![*segment];
+    /// Where the line of sight from `point` to the camera ends. This is the eye, except for
+    /// parallel projections, for which it's where the line of sight through the point crosses
+    /// the near plane, or the point itself if it lies in front of the near plane.
+    pub fn viewpoint(&self, point: WPoint3) -> WPoint3 {
+        if self.projection.is_parallel() {
+            let depth = self.distance_to(point) - self.znear;
+            point + self.backward() * depth.max(0.0)
+        } else {
+            self.eye
+        }
+    }
+
+    /// The point from which the silhouettes of curved shapes are seen.
+    pub fn silhouette_eye(&self) -> WPoint3 {
+        if self.projection.is_parallel() {
+            self.eye + self.backward() * (PARALLEL_EYE_DISTANCE * self.zfar.abs().max(1.0))
+        } else {
+            self.eye
+        }
+    }
+
+    /// The transformation from world space to the coordinates of the canvas that paths are
+    /// rendered onto, or `None` for curvilinear projections, which bend straight lines. Use
+    /// `project_segment` to project segments with any kind of projection.
+    pub fn canvas_transform(&self) -> Option<Transform3D<f64, WorldSpace, CameraSpace>> {
+        if self.projection.is_curvilinear() {
+            return None;
         }
+        Some(
+            self.matrix
+                .then_translate(Vector3D::new(1.0, 1.0, 0.0))
+                .then_scale(self.width / 2.0, self.height / 2.0, 0.0)
+                .with_destination(),
+        )
+    }
 
-        let true_chunk_len = segment_length / chunk_count as f64;
-        let true_chunk_len = f64::min(true_chunk_len, segment_length);
+    /// Projects a point onto the canvas, in the same coordinates as the paths returned by
+    /// `SceneCamera::render`. The `z` coordinate of the result is the point's depth, going from
+    /// 0 at the near plane to 1 at the far plane. Points behind the eye can't be projected,
+    /// except by curvilinear projections, which measure depth as the distance from the eye.
+    pub fn project(&self, point: WPoint3) -> Option<CPoint3> {
+        if self.projection.is_curvilinear() {
+            let local = self.view.transform_point3d(point)?.to_vector();
+            let distance = local.length();
+            if distance == 0.0 {
+                return None;
+            }
+            let (x, y) = self.project_direction(local / distance);
+            let depth = (distance - self.znear) / (self.zfar - self.znear);
+            return Some(CPoint3::new(x, y, depth));
+        }
 
-        let segment_dir = segment_diff.normalize();
-        let chunk_vec = segment_dir * true_chunk_len;
-        (0..chunk_count)
-            .map(|segment_ndx| {
-                let p1 = segment.p1 + (chunk_vec * (segment_ndx as f64));
-                let p2 = p1 + chunk_vec;
-                LineSegment::tagged(p1, p2, segment.tag)
+        let ndc = self.matrix.transform_point3d(point)?;
+        Some(CPoint3::new(
+            (ndc.x + 1.0) * self.width / 2.0,
+            (ndc.y + 1.0) * self.height / 2.0,
+            (ndc.z + 1.0) / 2.0,
+        ))
+    }
+
+    /// Projects a segment onto the canvas. Curvilinear projections bend it into a curve, which
+    /// is followed by as many straight segments as it takes to stay within a fraction of a
+    /// pixel of it. Only the parts of the segment inside of the view frustum are projected, and
+    /// the result is clipped to the canvas.
+    pub fn project_segment(
+        &self,
+        segment: &LineSegment<WorldSpace>,
+    ) -> Vec<LineSegment<CameraSpace>> {
+        let mut projected = Vec::new();
+        for clipped in self.clip_segment(segment) {
+            if let Some(transformation) = self.canvas_transform() {
+                projected.extend(clipped.transform(&transformation));
+            } else if let (Some(c1), Some(c2)) =
+                (self.project(clipped.p1), self.project(clipped.p2))
+            {
+                let (p1, p2) = ((clipped.p1, c1), (clipped.p2, c2));
+                self.project_curve(p1, p2, clipped.tag, 0, &mut projected);
+            }
+        }
+        projected
+            .iter()
+            .filter_map(|segment| self.clip_to_canvas(segment))
+            .collect()
+    }
+
+    /// Clips a segment to the camera's view frustum, leaving out the parts of it which lie
+    /// behind the near plane, beyond the far plane or outside of the field of view. This is done
+    /// in homogeneous clip space, before the perspective divide, so that segments which pass
+    /// behind the eye are clipped rather than turned inside out.
+    ///
+    /// Curvilinear projections see all around the eye, so segments are only clipped to lie
+    /// between the spheres around the eye which the near and far planes turn into. A segment
+    /// which passes through the near sphere is split in two.
+    pub fn clip_segment(&self, segment: &LineSegment<WorldSpace>) -> Vec<LineSegment<WorldSpace>> {
+        if !self.projection.is_curvilinear() {
+            // The frustum's planes are where x, y and z equal -w or w in clip space.
+            let distances = |p: WPoint3| {
+                let c = self.matrix.transform_point3d_homogeneous(p);
+                [
+                    c.w + c.x,
+                    c.w - c.x,
+                    c.w + c.y,
+                    c.w - c.y,
+                    c.w + c.z,
+                    c.w - c.z,
+                ]
+            };
+            let (d1, d2) = (distances(segment.p1), distances(segment.p2));
+            let range = clip_range(d1.iter().copied().zip(d2.iter().copied()));
+            return range
+                .map(|(t1, t2)| segment.slice(t1, t2))
+                .into_iter()
+                .collect();
+        }
+
+        let offset = segment.p1 - self.eye;
+        let dir = segment.p2 - segment.p1;
+        let length = dir.square_length();
+        if length == 0.0 {
+            let distance = offset.length();
+            return if (self.znear..=self.zfar).contains(&distance) {
+                vec![*segment]
+            } else {
+                Vec::new()
+            };
+        }
+
+        // The fractions of the way along the segment where its line crosses a sphere around the
+        // eye, if it does.
+        let crossings = |radius: f64| {
+            let b = offset.dot(dir);
+            let discriminant = b * b - length * (offset.square_length() - radius * radius);
+            (discriminant >= 0.0).then(|| {
+                let root = discriminant.sqrt();
+                ((-b - root) / length, (-b + root) / length)
             })
+        };
+        let (t1, t2) = match crossings(self.zfar) {
+            Some((t1, t2)) => (t1.max(0.0), t2.min(1.0)),
+            None => return Vec::new(),
+        };
+        let ranges = match crossings(self.znear) {
+            Some((n1, n2)) => vec![(t1, t2.min(n1)), (t1.max(n2), t2)],
+            None => vec![(t1, t2)],
+        };
+        ranges
+            .into_iter()
+            .filter(|(t1, t2)| t1 < t2)
+            .map(|(t1, t2)| segment.slice(t1, t2))
             .collect()
     }
+
+    /// Clips a projected segment to the canvas.
+    fn clip_to_canvas(
+        &self,
+        segment: &LineSegment<CameraSpace>,
+    ) -> Option<LineSegment<CameraSpace>> {
+        let (p1, p2) = (segment.p1, segment.p2);
+        let distances = [
+            (p1.x, p2.x),
+            (self.width - p1.x, self.width - p2.x),
+            (p1.y, p2.y),
+            (self.height - p1.y, self.height - p2.y),
+        ];
+        clip_range(distances.iter().copied()).map(|(t1, t2)| segment.slice(t1, t2))
+    }
+
+    /// Splits the segment between two points, along with where they're projected to, in half
+    /// until its pieces follow the curve it projects to.
+    fn project_curve(
+        &self,
+        (p1, c1): (WPoint3, CPoint3),
+        (p2, c2): (WPoint3, CPoint3),
+        tag: usize,
+        depth: usize,
+        projected: &mut Vec<LineSegment<CameraSpace>>,
+    ) {
+        let midpoint = p1.lerp(p2, 0.5);
+        let straight = self.project(midpoint).map(|cm| {
+            let stray = (cm.to_2d() - c1.to_2d().lerp(c2.to_2d(), 0.5)).length();
+            let angle = (p1 - self.eye).angle_to(p2 - self.eye).to_degrees();
+            (stray <= CURVE_TOLERANCE && angle <= CURVE_MAX_ANGLE, cm)
+        });
+        match straight {
+            Some((true, _)) => projected.push(LineSegment::tagged(c1, c2, tag)),
+            Some((false, cm)) if depth < CURVE_MAX_DEPTH => {
+                let middle = (midpoint, cm);
+                self.project_curve((p1, c1), middle, tag, depth + 1, projected);
+                self.project_curve(middle, (p2, c2), tag, depth + 1, projected);
+            }
+            _ => {}
+        }
+    }
+
+    /// Where a normalized direction in the camera's space ends up on the canvas, for curvilinear
+    /// projections.
+    fn project_direction(&self, dir: CVec3) -> (f64, f64) {
+        let (half_width, half_height) = (self.width / 2.0, self.height / 2.0);
+        let radial = |r: f64| {
+            // The distance from the middle of the canvas, in half heights of the canvas, at the
+            // given angle from the view direction.
+            let xy = (dir.x * dir.x + dir.y * dir.y).sqrt();
+            let (cos, sin) = if xy > 0.0 {
+                (dir.x / xy, dir.y / xy)
+            } else {
+                (1.0, 0.0)
+            };
+            (
+                half_width + r * cos * half_height,
+                half_height + r * sin * half_height,
+            )
+        };
+        let theta = (-dir.z).clamp(-1.0, 1.0).acos();
+        match self.projection {
+            Projection::Fisheye { fov } => radial(theta / (fov.to_radians() / 2.0)),
+            Projection::Stereographic { fov } => {
+                radial((theta / 2.0).tan() / (fov.to_radians() / 4.0).tan())
+            }
+            Projection::Equirectangular { fovx, fovy } => {
+                let longitude = dir.x.atan2(-dir.z);
+                let latitude = dir.y.clamp(-1.0, 1.0).asin();
+                (
+                    half_width + longitude / (fovx.to_radians() / 2.0) * half_width,
+                    half_height + latitude / (fovy.to_radians() / 2.0) * half_height,
+                )
+            }
+            _ => unreachable!("only curvilinear projections project directions"),
+        }
+    }
+
+    /// The normalized direction in the camera's space which ends up at the given point of the
+    /// canvas, for curvilinear projections.
+    fn unproject_direction(&self, x: f64, y: f64) -> CVec3 {
+        let (half_width, half_height) = (self.width / 2.0, self.height / 2.0);
+        let radial = |theta: f64| {
+            let (u, v) = (
+                (x - half_width) / half_height,
+                (y - half_height) / half_height,
+            );
+            let r = (u * u + v * v).sqrt();
+            let (cos, sin) = if r > 0.0 { (u / r, v / r) } else { (1.0, 0.0) };
+            CVec3::new(theta.sin() * cos, theta.sin() * sin, -theta.cos())
+        };
+        let r = ((x - half_width).powi(2) + (y - half_height).powi(2)).sqrt() / half_height;
+        match self.projection {
+            Projection::Fisheye { fov } => radial(r * fov.to_radians() / 2.0),
+            Projection::Stereographic { fov } => {
+                radial(2.0 * (r * (fov.to_radians() / 4.0).tan()).atan())
+            }
+            Projection::Equirectangular { fovx, fovy } => {
+                let longitude = (x - half_width) / half_width * fovx.to_radians() / 2.0;
+                let latitude = (y - half_height) / half_height * fovy.to_radians() / 2.0;
+                CVec3::new(
+                    latitude.cos() * longitude.sin(),
+                    latitude.sin(),
+                    -latitude.cos() * longitude.cos(),
+                )
+            }
+            _ => unreachable!("only curvilinear projections unproject directions"),
+        }
+    }
+
+    /// Finds the ray which passes through the given point of the canvas, in the same coordinates
+    /// as the paths returned by `SceneCamera::render`. The ray starts at the eye, or for parallel
+    /// projections, where it crosses the plane through the eye which faces the view direction.
+    /// Its bounded part runs from the near plane to the far plane, so distances along it, such as
+    /// those of hits found by `Scene::pick`, are measured from the camera.
+    pub fn unproject(&self, x: f64, y: f64) -> Ray {
+        if self.projection.is_curvilinear() {
+            let to_world = self.view.inverse().unwrap();
+            let dir = to_world.transform_vector3d(self.unproject_direction(x, y));
+            return Ray::bounded(self.eye, dir, self.znear, self.zfar);
+        }
+
+        let inverse = self.matrix.inverse().unwrap();
+        let ndc_x = x / (self.width / 2.0) - 1.0;
+        let ndc_y = y / (self.height / 2.0) - 1.0;
+
+        let near = inverse
+            .transform_point3d(Point3D::new(ndc_x, ndc_y, -1.0))
+            .unwrap();
+        let far = inverse
+            .transform_point3d(Point3D::new(ndc_x, ndc_y, 1.0))
+            .unwrap();
+        let origin = if self.projection.is_parallel() {
+            let near_depth = (near - self.eye).dot(self.forward());
+            let far_depth = (far - self.eye).dot(self.forward());
+            near - (far - near) * (near_depth / (far_depth - near_depth))
+        } else {
+            self.eye
+        };
+        let (to_near, to_far) = (near - origin, far - origin);
+        Ray::bounded(
+            origin,
+            to_far.normalize(),
+            to_near.length(),
+            to_far.length(),
+        )
+    }
+
+    /// Returns whether or not the point lies within the camera's view frustum, between the near
+    /// and far planes.
+    pub fn in_frustum(&self, point: WPoint3) -> bool {
+        if self.projection.is_curvilinear() {
+            return self.project(point).is_some_and(|p| {
+                (0.0..=self.width).contains(&p.x)
+                    && (0.0..=self.height).contains(&p.y)
+                    && (0.0..=1.0).contains(&p.z)
+            });
+        }
+
+        self.matrix
+            .transform_point3d(point)
+            .is_some_and(|ndc| ndc.to_array().iter().all(|c| (-1.0..=1.0).contains(c)))
+    }
+
+    /// Returns whether or not the box may overlap the camera's view frustum. Boxes which lie
+    /// just outside of it, near its edges, may be reported as overlapping it, but no box which
+    /// does overlap it is missed. For curvilinear projections, only boxes which lie beyond the
+    /// far plane all around the eye are known not to overlap it.
+    pub fn overlaps_frustum(&self, aabb: AABB<WorldSpace>) -> bool {
+        if self.projection.is_curvilinear() {
+            let nearest = self.eye.clamp(aabb.min, aabb.max);
+            return (nearest - self.eye).length() <= self.zfar;
+        }
+
+        let corners: Vec<_> = (0..8)
+            .map(|i| {
+                let corner = Point3D::new(
+                    if i & 1 == 0 { aabb.min.x } else { aabb.max.x },
+                    if i & 2 == 0 { aabb.min.y } else { aabb.max.y },
+                    if i & 4 == 0 { aabb.min.z } else { aabb.max.z },
+                );
+                self.matrix.transform_point3d_homogeneous(corner)
+            })
+            .collect();
+
+        // The box lies outside of the frustum if all of its corners lie outside of the same one
+        // of the frustum's planes. In clip space, those are where x, y and z equal -w or w.
+        let outside =
+            |side: fn(&HomogeneousVector<f64, CanvasSpace>) -> bool| corners.iter().all(side);
+        !(outside(|c| c.x < -c.w)
+            || outside(|c| c.x > c.w)
+            || outside(|c| c.y < -c.w)
+            || outside(|c| c.y > c.w)
+            || outside(|c| c.z < -c.w)
+            || outside(|c| c.z > c.w))
+    }
+
+    /// How long, in pixels, the chunks which segments are chopped into are on the canvas.
+    pub fn chunk_size(&self) -> f64 {
+        self.chunk_size
+    }
+
+    /// Sets how long, in pixels, the chunks which segments are chopped into are on the canvas.
+    /// Whether a chunk is visible is decided at its middle, so smaller chunks follow the edges
+    /// of occluders more closely, while larger ones render faster. Sizes which aren't positive
+    /// and finite fall back to the default of one pixel.
+    pub fn with_chunk_size(self, pixels: f64) -> Camera {
+        let chunk_size = Some(pixels)
+            .filter(|pixels| pixels.is_finite() && *pixels > 0.0)
+            .unwrap_or(DEFAULT_CHUNK_SIZE);
+        Camera { chunk_size, ..self }
+    }
+
+    /// How close, in pixels, where visibility changes between neighboring chunks is found, or
+    /// `None` if chunks are only either visible or hidden as a whole.
+    pub fn boundary_tolerance(&self) -> Option<f64> {
+        self.boundary_tolerance
+    }
+
+    /// Makes `SceneCamera::render` look for where visibility changes between neighboring chunks,
+    /// by bisecting the stretch between their middles until it's at most `pixels` long on the
+    /// canvas. Lines then end at the silhouettes of the shapes which hide them, rather than at
+    /// the ends of chunks, so chunks can be much larger. Shapes which fit between the middles
+    /// of two chunks may still be missed.
+    pub fn with_boundary_tolerance(self, pixels: f64) -> Camera {
+        Camera {
+            boundary_tolerance: Some(pixels),
+            ..self
+        }
+    }
+
+    /// Chops a line segment into chunks which are each about `chunk_size` pixels long on the
+    /// canvas, however far they are from the camera. Segments which are shorter than that are
+    /// left whole.
+    pub fn chop_segment(&self, segment: &LineSegment<WorldSpace>) -> Vec<LineSegment<WorldSpace>> {
+        let (p1, p2) = (segment.p1, segment.p2);
+        let chunks = |pixels: f64| usize::max((pixels / self.chunk_size).round() as usize, 1);
+
+        // The fractions of the way along the segment where its chunks start and end.
+        let fractions: Vec<f64> = if self.projection.is_curvilinear() {
+            // Chunks are spread evenly over the angle which the segment spans as seen from the
+            // eye, which each pixel covers about as much of everywhere on the canvas.
+            let (o1, o2) = (p1 - self.eye, p2 - self.eye);
+            let angle = o1.cross(o2).length().atan2(o1.dot(o2));
+            let count = chunks(angle / self.pixel_angle());
+            let (d1, d2) = (o1.length(), o2.length());
+            (0..=count)
+                .map(|i| match i {
+                    0 => 0.0,
+                    i if i == count => 1.0,
+                    i => {
+                        // The line of sight at this angle from `o1` splits the triangle between
+                        // the eye and the segment into two, whose areas have the same ratio as
+                        // the parts of the segment on either side of it.
+                        let phi = angle * i as f64 / count as f64;
+                        let (a1, a2) = (d1 * phi.sin(), d2 * (angle - phi).sin());
+                        a1 / (a1 + a2)
+                    }
+                })
+                .collect()
+        } else {
+            let (c1, c2) = match (self.project(p1), self.project(p2)) {
+                (Some(c1), Some(c2)) => (c1, c2),
+                _ => return vec![*segment],
+            };
+            let count = chunks((c2.to_2d() - c1.to_2d()).length());
+            // Chunks are spread evenly over the canvas, and perspective bunches them up along
+            // the segment where it's far away. A fraction `s` of the way across the canvas is a
+            // fraction `s * w1 / (s * w1 + (1 - s) * w2)` of the way along the segment.
+            let w1 = self.matrix.transform_point3d_homogeneous(p1).w;
+            let w2 = self.matrix.transform_point3d_homogeneous(p2).w;
+            (0..=count)
+                .map(|i| {
+                    let s = i as f64 / count as f64;
+                    s * w1 / (s * w1 + (1.0 - s) * w2)
+                })
+                .collect()
+        };
+
+        fractions
+            .windows(2)
+            .map(|t| segment.slice(t[0], t[1]))
+            .collect()
+    }
+
+    /// The angle covered by a pixel in the middle of the canvas, for curvilinear projections.
+    fn pixel_angle(&self) -> f64 {
+        match self.projection {
+            Projection::Fisheye { fov } => fov.to_radians() / self.height,
+            Projection::Stereographic { fov } => 4.0 * (fov.to_radians() / 4.0).tan() / self.height,
+            Projection::Equirectangular { fovx, fovy } => f64::min(
+                fovx.to_radians() / self.width,
+                fovy.to_radians() / self.height,
+            ),
+            _ => unreachable!("only curvilinear projections cover angles with pixels"),
+        }
+    }
 }
 
 #[rustfmt::skip]
@@ -142,51 +806,196 @@ fn frustum(
     )
 }
 
+#[rustfmt::skip]
+fn orthographic(
+    l: f64,
+    r: f64,
+    b: f64,
+    t: f64,
+    n: f64,
+    f: f64,
+) -> Transform3D<f64, CameraSpace, CanvasSpace> {
+    let t2 = r - l;
+    let t3 = t - b;
+    let t4 = f - n;
+
+    Transform3D::from_array(
+        Transform3D::<f64, CameraSpace, CanvasSpace>::new(
+            2.0 / t2, 0.0,      0.0,       -(r + l) / t2,
+            0.0,      2.0 / t3, 0.0,       -(t + b) / t3,
+            0.0,      0.0,      -2.0 / t4, -(f + n) / t4,
+            0.0,      0.0,      0.0,       1.0,
+        )
+        .to_array_transposed(),
+    )
+}
+
+/// Shears camera space so that points are moved across the canvas in proportion to how far they
+/// lie behind the plane at `depth`, along a line at `angle` degrees, by `scale` times that
+/// distance.
+#[rustfmt::skip]
+fn oblique(angle: f64, scale: f64, depth: f64) -> CCTransform {
+    let angle = angle.to_radians();
+    let sx = -scale * angle.cos();
+    let sy = -scale * angle.sin();
+
+    Transform3D::from_array(
+        CCTransform::new(
+            1.0, 0.0, sx,  sx * depth,
+            0.0, 1.0, sy,  sy * depth,
+            0.0, 0.0, 1.0, 0.0,
+            0.0, 0.0, 0.0, 1.0,
+        )
+        .to_array_transposed(),
+    )
+}
+
 impl LookingCamera {
     pub fn perspective(self, fovy: f64, width: f64, height: f64, znear: f64, zfar: f64) -> Camera {
-        let aspect = width / height;
-        let ymax = znear * (fovy * std::f64::consts::PI / 360.0).tan();
-        let xmax = ymax * aspect;
-
-        let frustum = frustum(-xmax, xmax, -ymax, ymax, znear, zfar);
-        let matrix = self.matrix.then(&frustum);
+        let projection = Projection::Perspective {
+            fovy,
+            shift_x: 0.0,
+            shift_y: 0.0,
+        };
+        self.with_projection(projection, width, height, znear, zfar)
+    }
 
-        let effective_width = width;
-        let effective_height = height;
-        let znear_width = 2.0 * xmax;
-        let znear_height = 2.0 * ymax;
-        let est_min_pix_height = znear_height / effective_height;
-        let est_min_pix_width = znear_width / effective_width;
+    /// Creates a perspective camera which looks level, at right angles to the up direction, so
+    /// that vertical lines stay vertical and parallel on the canvas. Rather than by tilting the
+    /// camera, the point it was looking at is brought back into the middle of the canvas by
+    /// shifting the lens. Cameras which look straight up or down can't be leveled, and are left
+    /// as they are.
+    pub fn two_point_perspective(
+        self,
+        fovy: f64,
+        width: f64,
+        height: f64,
+        znear: f64,
+        zfar: f64,
+    ) -> Camera {
+        let up = self.up.normalize();
+        let target = self.center - self.eye.to_vector();
+        let level = target - up * target.dot(up);
+        let depth = level.length();
+        if depth <= target.length() * 1.0e-9 {
+            return self.perspective(fovy, width, height, znear, zfar);
+        }
 
-        let min_step_size = f64::min(est_min_pix_height, est_min_pix_width);
+        let height_above = target.dot(up) / depth;
+        let shift_y = height_above / (fovy * std::f64::consts::PI / 360.0).tan() / 2.0;
+        let projection = Projection::Perspective {
+            fovy,
+            shift_x: 0.0,
+            shift_y,
+        };
+        Camera::look_at(self.eye, self.eye.to_vector() + level, self.up)
+            .with_projection(projection, width, height, znear, zfar)
+    }
 
-        let zfar_ymax = zfar * (fovy * std::f64::consts::PI / 360.0).tan();
-        let zfar_xmax = zfar_ymax * aspect;
+    /// Creates a camera with parallel lines of sight, which covers a rectangle of `view_width`
+    /// by `view_height` world units around the view direction. If that rectangle's aspect ratio
+    /// differs from the canvas', the drawing is stretched to fit it.
+    pub fn orthographic(
+        self,
+        view_width: f64,
+        view_height: f64,
+        width: f64,
+        height: f64,
+        znear: f64,
+        zfar: f64,
+    ) -> Camera {
+        let projection = Projection::Orthographic {
+            view_width,
+            view_height,
+        };
+        self.with_projection(projection, width, height, znear, zfar)
+    }
 
-        let zfar_width = 2.0 * zfar_xmax;
-        let zfar_height = 2.0 * zfar_ymax;
-        let est_max_pix_height = zfar_height / effective_height;
-        let est_max_pix_width = zfar_width / effective_width;
+    /// Creates a camera which projects the world onto its canvas in the given way.
+    pub fn with_projection(
+        self,
+        projection: Projection,
+        width: f64,
+        height: f64,
+        znear: f64,
+        zfar: f64,
+    ) -> Camera {
+        let aspect = width / height;
+        let projection_matrix = match projection {
+            Projection::Perspective {
+                fovy,
+                shift_x,
+                shift_y,
+            } => {
+                let ymax = znear * (fovy * std::f64::consts::PI / 360.0).tan();
+                let xmax = ymax * aspect;
 
-        let max_step_size = f64::min(est_max_pix_height, est_max_pix_width);
+                // Shifting the lens moves the frustum across the near plane without turning it.
+                let (dx, dy) = (2.0 * xmax * shift_x, 2.0 * ymax * shift_y);
+                frustum(-xmax + dx, xmax + dx, -ymax + dy, ymax + dy, znear, zfar)
+            }
+            Projection::Orthographic {
+                view_width,
+                view_height,
+            } => parallel(
+                CCTransform::identity(),
+                view_width,
+                view_height,
+                znear,
+                zfar,
+            ),
+            Projection::Oblique {
+                view_width,
+                view_height,
+                angle,
+                scale,
+            } => {
+                let depth = (self.center - self.eye.to_vector()).length();
+                parallel(
+                    oblique(angle, scale, depth),
+                    view_width,
+                    view_height,
+                    znear,
+                    zfar,
+                )
+            }
+            // Curvilinear projections can't be written as a transformation.
+            Projection::Fisheye { .. }
+            | Projection::Stereographic { .. }
+            | Projection::Equirectangular { .. } => Transform3D::identity(),
+        };
 
         Camera {
             eye: self.eye,
-            _center: self.center,
-            _up: self.up,
-            _fovy: fovy,
+            center: self.center,
+            up: self.up,
+            projection,
             width,
             height,
-            _aspect: aspect,
+            aspect,
             znear,
             zfar,
-            min_step_size,
-            max_step_size,
-            matrix,
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            boundary_tolerance: None,
+            view: self.matrix,
+            matrix: self.matrix.then(&projection_matrix),
         }
     }
 }
 
+/// Projects camera space onto the canvas along parallel lines of sight, after transforming it by
+/// `shear`.
+fn parallel(
+    shear: CCTransform,
+    view_width: f64,
+    view_height: f64,
+    znear: f64,
+    zfar: f64,
+) -> Transform3D<f64, CameraSpace, CanvasSpace> {
+    let (xmax, ymax) = (view_width / 2.0, view_height / 2.0);
+    shear.then(&orthographic(-xmax, xmax, -ymax, ymax, znear, zfar))
+}
+
 pub struct SceneCamera<'s> {
     camera: Camera,
     scene: &'s Scene,
@@ -196,41 +1005,104 @@ pub struct SceneCamera<'s> {
 
 impl<'a> SceneCamera<'a> {
     fn clip_filter(&self, path: &LineSegment<WorldSpace>) -> bool {
-        let (p1, p2) = (path.p1, path.p2);
-        let midpoint = p1 + ((p2 - p1) / 2.0);
-        self.scene.visible(self.camera.eye, midpoint)
+        self.visible_at(path.p1.lerp(path.p2, 0.5))
+    }
+
+    fn visible_at(&self, point: WPoint3) -> bool {
+        self.scene.visible(self.camera.viewpoint(point), point)
+    }
+
+    /// The visible parts of the chunks a path was chopped into. Where the camera has a boundary
+    /// tolerance, visible chunks are stretched or cut short to where visibility changes between
+    /// them and their hidden neighbors.
+    fn visible_chunks(&self, chunks: &[LineSegment<WorldSpace>]) -> Vec<LineSegment<WorldSpace>> {
+        let visible: Vec<bool> = chunks
+            .par_iter()
+            .map(|chunk| self.clip_filter(chunk))
+            .collect();
+        let tolerance = match self.camera.boundary_tolerance {
+            Some(tolerance) => tolerance,
+            None => {
+                return chunks
+                    .iter()
+                    .zip(&visible)
+                    .filter(|(_, &visible)| visible)
+                    .map(|(chunk, _)| *chunk)
+                    .collect()
+            }
+        };
+
+        // Where visibility changes between each chunk and the next, if it does. Chunks which
+        // don't meet, such as those on either side of the near plane around the eye of a
+        // curvilinear projection, aren't neighbors.
+        let boundaries: Vec<Option<WPoint3>> = chunks
+            .par_windows(2)
+            .zip(visible.par_windows(2))
+            .map(|(pair, visible)| {
+                if visible[0] == visible[1] || pair[0].p2 != pair[1].p1 {
+                    return None;
+                }
+                let middles = (
+                    pair[0].p1.lerp(pair[0].p2, 0.5),
+                    pair[1].p1.lerp(pair[1].p2, 0.5),
+                );
+                Some(if visible[0] {
+                    self.find_boundary(middles.0, middles.1, tolerance)
+                } else {
+                    self.find_boundary(middles.1, middles.0, tolerance)
+                })
+            })
+            .collect();
+
+        chunks
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| visible[*i])
+            .map(|(i, chunk)| {
+                let before = i.checked_sub(1).and_then(|i| boundaries[i]);
+                let after = boundaries.get(i).copied().flatten();
+                LineSegment::tagged(
+                    before.unwrap_or(chunk.p1),
+                    after.unwrap_or(chunk.p2),
+                    chunk.tag,
+                )
+            })
+            .collect()
+    }
+
+    /// Bisects the stretch between a visible and a hidden point until it's at most `tolerance`
+    /// pixels long on the canvas, and returns its middle.
+    fn find_boundary(&self, mut visible: WPoint3, mut hidden: WPoint3, tolerance: f64) -> WPoint3 {
+        for _ in 0..BOUNDARY_MAX_DEPTH {
+            let length = match (self.camera.project(visible), self.camera.project(hidden)) {
+                (Some(c1), Some(c2)) => (c2.to_2d() - c1.to_2d()).length(),
+                _ => break,
+            };
+            if length <= tolerance {
+                break;
+            }
+            let middle = visible.lerp(hidden, 0.5);
+            if self.visible_at(middle) {
+                visible = middle;
+            } else {
+                hidden = middle;
+            }
+        }
+        visible.lerp(hidden, 0.5)
     }
 
     pub fn render(&self) -> Vec<LineSegment<CameraSpace>> {
         info!(
-            "Clipping occluded and distant segment chunks, started with {} segments",
+            "Clipping occluded segment chunks, started with {} segments",
             self.path_count
         );
 
-        let transformation: Transform3D<f64, WorldSpace, CameraSpace> = self
-            .camera
-            .matrix
-            .then_translate(Vector3D::new(1.0, 1.0, 0.0))
-            .then_scale(self.camera.width / 2.0, self.camera.height / 2.0, 0.0)
-            .with_destination();
-
         let paths: Vec<_> = self
             .paths
             .par_iter()
-            .map(|path_group| {
-                path_group
-                    .par_iter()
-                    .filter(|path| {
-                        let close_enough = (path.p1.to_vector() - self.camera.eye.to_vector())
-                            .length()
-                            < self.camera.zfar;
-                        close_enough && self.clip_filter(path)
-                    })
-                    .cloned()
-                    .collect::<Vec<_>>()
-            })
+            .map(|path_group| self.visible_chunks(path_group))
             .flat_map(|path_group| simplify_segments(&path_group, 1.0e-6))
-            .filter_map(|path| path.transform(&transformation))
+            .flat_map(|path| self.camera.project_segment(&path))
             .collect();
 
         info!("{} paths remain after clipping", paths.len());
@@ -241,27 +1113,87 @@ impl<'a> SceneCamera<'a> {
 
 #[derive(Debug)]
 pub struct Scene {
-    geometry: Vec<Arc<dyn Shape<WorldSpace>>>,
     bvh: BVHTree<WorldSpace>,
 }
 
 impl Scene {
+    /// Creates a scene from the given geometry. Degenerate shapes, such as triangles whose
+    /// corners lie on a line, are left out with a warning.
+    ///
+    /// The bounding volume hierarchy is built with the default strategy, which splits nodes by
+    /// the surface area heuristic. Scenes used to be split at the median instead; pass
+    /// `BVHStrategy::Median` to `with_strategy` for that.
     pub fn new(geometry: Vec<Box<dyn Shape<WorldSpace>>>) -> Scene {
+        Self::with_strategy(geometry, BVHStrategy::default())
+    }
+
+    /// Creates a scene from the given geometry, unless any of it is degenerate, in which case the
+    /// first degenerate shape is reported.
+    pub fn try_new(geometry: Vec<Box<dyn Shape<WorldSpace>>>) -> Result<Scene, DegenerateShape> {
+        for (ndx, shape) in geometry.iter().enumerate() {
+            bvh::check_shape(ShapeId(ndx), shape.as_ref())?;
+        }
+        Ok(Self::new(geometry))
+    }
+
+    /// Creates a scene whose bounding volume hierarchy is built using the given strategy.
+    pub fn with_strategy(
+        geometry: Vec<Box<dyn Shape<WorldSpace>>>,
+        strategy: BVHStrategy,
+    ) -> Scene {
+        let geometry: Vec<_> = geometry.into_iter().map(Arc::from).collect();
+        let bvh = BVHTree::with_strategy(&geometry, strategy);
+        Scene { bvh }
+    }
+
+    /// Adds geometry to the scene, returning the ids it was given. Ids of removed geometry are
+    /// not reused.
+    pub fn insert(&mut self, geometry: Vec<Box<dyn Shape<WorldSpace>>>) -> Vec<ShapeId> {
         let geometry: Vec<_> = geometry.into_iter().map(Arc::from).collect();
-        let bvh = BVHTree::new(&geometry);
-        Scene { geometry, bvh }
+        self.bvh.insert(&geometry)
+    }
+
+    /// Removes the geometry with the given ids from the scene.
+    pub fn remove(&mut self, ids: &[ShapeId]) {
+        self.bvh.remove(ids)
+    }
+
+    /// Replaces geometry which has moved, e.g. between the frames of an animation, by its new
+    /// version, which is re-bucketed into the existing bounding volume hierarchy as with
+    /// `BVHTree::rebucket`. This is much faster than creating a new scene when only some of the
+    /// geometry moves, or when it only moves a little. Geometry which has become degenerate is
+    /// removed from the scene, and returned.
+    pub fn rebucket(
+        &mut self,
+        moved: Vec<(ShapeId, Box<dyn Shape<WorldSpace>>)>,
+    ) -> Vec<DegenerateShape> {
+        let moved: Vec<_> = moved
+            .into_iter()
+            .map(|(id, shape)| (id, Arc::from(shape)))
+            .collect();
+        self.bvh.rebucket(&moved)
     }
 
-    pub fn attach_camera(&self, camera: Camera) -> SceneCamera {
+    pub fn attach_camera(&self, camera: Camera) -> SceneCamera<'_> {
         info!("Caching line segment chunks based on new camera attachment");
-        let paths: Vec<Vec<LineSegment<WorldSpace>>> = self
-            .geometry
+        let geometry: Vec<_> = self.bvh.shapes().map(|(_, shape)| shape).collect();
+        let paths: Vec<Vec<LineSegment<WorldSpace>>> = geometry
             .par_iter()
-            .map(|s| s.paths())
+            .map(|s| {
+                let mut paths = s.paths();
+                paths.extend(s.silhouette_paths(camera.silhouette_eye()));
+                paths
+            })
             .flat_map(|paths| {
                 paths
                     .par_iter()
-                    .map(|path| camera.chop_segment(path))
+                    .map(|path| {
+                        camera
+                            .clip_segment(path)
+                            .iter()
+                            .flat_map(|clipped| camera.chop_segment(clipped))
+                            .collect()
+                    })
                     .collect::<Vec<_>>()
             })
             .collect();
@@ -279,22 +1211,774 @@ impl Scene {
         }
     }
 
-    /// Find's the closest intersection point to geometry in the scene, if any
-    fn intersects(&self, ray: Ray) -> Option<HitData> {
-        self.bvh.intersects(ray)
+    /// Returns the shape with the given id, which is its position in the list of geometry that
+    /// the scene was created with, or the id it was given when inserted.
+    pub fn shape(&self, id: ShapeId) -> Option<&dyn Shape<WorldSpace>> {
+        self.bvh.shape(id).map(|shape| &**shape)
+    }
+
+    /// The bounding volume hierarchy which holds the scene's geometry, e.g. for finding out why it
+    /// renders slowly with `BVHTree::stats`, or drawing it with `BVHTree::debug_paths`.
+    pub fn bvh(&self) -> &BVHTree<WorldSpace> {
+        &self.bvh
+    }
+
+    /// Finds the geometry under the given point of the canvas, as seen by `camera`. The point is
+    /// in the same coordinates as the paths returned by `SceneCamera::render`. The distance to
+    /// the hit is measured from where `Camera::unproject` starts the ray through the point.
+    pub fn pick(&self, camera: &Camera, x: f64, y: f64) -> Option<HitData> {
+        self.intersects(camera.unproject(x, y))
+    }
+
+    /// Find's the closest intersection point to geometry in the scene along the bounded part of
+    /// `ray`, if any
+    pub fn intersects(&self, ray: Ray) -> Option<HitData> {
+        self.bvh.closest_hit(ray)
+    }
+
+    /// Returns whether or not anything in the scene lies along the bounded part of `ray`, between
+    /// its `tmin` and `tmax`. This is cheaper than finding the closest intersection, since the
+    /// search stops as soon as any hit is found.
+    pub fn occluded(&self, ray: Ray) -> bool {
+        self.bvh.any_hit(ray).is_some()
     }
 
     /// Returns whether or not the given camera has a clear line of sight to a given point.
-    fn visible(&self, from: WPoint3, point: WPoint3) -> bool {
+    ///
+    /// The point usually lies on the surface of some shape, so the ray towards the camera starts
+    /// a tiny distance away from it to keep from striking that surface. The distance is relative
+    /// to the size of the scene, so that very small and very large scenes work equally well.
+    pub fn visible(&self, from: WPoint3, point: WPoint3) -> bool {
         let v = from - point;
-        let r = Ray::new(point, v.normalize());
+        let dist = v.length();
+        let size = self
+            .bvh
+            .bounded_box()
+            .map(|aabb| (aabb.max - aabb.min).length())
+            .filter(|size| size.is_finite() && *size > 0.0)
+            .unwrap_or(dist);
+        let offset = VISIBILITY_OFFSET * size;
+        if dist <= 2.0 * offset {
+            return true;
+        }
+
+        !self.occluded(Ray::bounded(point, v / dist, offset, dist - offset))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::shapes::RectPrism;
+    use euclid::approxeq::ApproxEq;
+
+    #[test]
+    fn test_visible() {
+        // small scenes far away from the origin are no different from ones around it
+        let scenes = [1.0e-3, 1.0, 1.0e3]
+            .iter()
+            .flat_map(|&scale| [(scale, 0.0), (scale, 1.0e4)]);
+        for (scale, position) in scenes {
+            let position = WVec3::splat(position);
+            let scene = Scene::new(vec![Box::new(RectPrism::new(
+                position,
+                position + WVec3::splat(scale),
+            ))]);
+            let at = |x, y, z| WPoint3::new(x, y, z) * scale + position;
+            let eye = at(3.0, 2.0, 4.0);
+
+            // edges facing the eye, lying exactly on the surface
+            assert!(scene.visible(eye, at(0.5, 1.0, 1.0)));
+            assert!(scene.visible(eye, at(1.0, 0.5, 1.0)));
+
+            // edges hidden behind the prism itself
+            assert!(!scene.visible(eye, at(0.5, 0.0, 0.0)));
+            assert!(!scene.visible(eye, at(0.0, 0.5, 0.0)));
+        }
+    }
+
+    #[test]
+    fn test_occluded() {
+        let scene = Scene::new(vec![
+            Box::new(RectPrism::new(WVec3::splat(0.0), WVec3::splat(1.0))),
+            Box::new(RectPrism::new(
+                WVec3::new(3.0, 0.0, 0.0),
+                WVec3::new(4.0, 1.0, 1.0),
+            )),
+        ]);
+        let from = WPoint3::new(-1.0, 0.5, 0.5);
+
+        assert!(scene.occluded(Ray::between(from, WPoint3::new(2.0, 0.5, 0.5))));
+        assert!(!scene.occluded(Ray::between(from, WPoint3::new(-0.5, 0.5, 0.5))));
+
+        // starts past the first prism and ends before the second
+        let ray = Ray::bounded(from, WVec3::new(1.0, 0.0, 0.0), 2.5, 3.5);
+        assert!(!scene.occluded(ray));
+        assert_eq!(scene.intersects(ray), None);
+
+        let ray = Ray::bounded(from, WVec3::new(1.0, 0.0, 0.0), 2.5, 10.0);
+        assert!(scene.occluded(ray));
+        assert_eq!(scene.intersects(ray).map(|hit| hit.dist_to), Some(4.0));
+    }
+
+    #[test]
+    fn test_scene_updates() {
+        let mut scene = Scene::new(vec![
+            Box::new(RectPrism::new(WVec3::splat(0.0), WVec3::splat(1.0))),
+            Box::new(RectPrism::new(
+                WVec3::new(3.0, 0.0, 0.0),
+                WVec3::new(4.0, 1.0, 1.0),
+            )),
+        ]);
+        let ray = Ray::new(WPoint3::new(-1.0, 0.5, 0.5), WVec3::new(1.0, 0.0, 0.0));
+
+        // move the first prism out of the way of the ray
+        let degenerate = scene.rebucket(vec![(
+            ShapeId(0),
+            Box::new(RectPrism::new(
+                WVec3::new(0.0, 2.0, 0.0),
+                WVec3::new(1.0, 3.0, 1.0),
+            )),
+        )]);
+        assert!(degenerate.is_empty());
+        let hit = scene.intersects(ray).unwrap();
+        assert_eq!((hit.dist_to, hit.shape_id), (4.0, Some(ShapeId(1))));
+
+        let ids = scene.insert(vec![Box::new(RectPrism::new(
+            WVec3::new(1.5, 0.0, 0.0),
+            WVec3::new(2.0, 1.0, 1.0),
+        ))]);
+        assert_eq!(ids, vec![ShapeId(2)]);
+        let hit = scene.intersects(ray).unwrap();
+        assert_eq!((hit.dist_to, hit.shape_id), (2.5, Some(ShapeId(2))));
+
+        scene.remove(&[ShapeId(1), ShapeId(2)]);
+        assert!(scene.shape(ShapeId(1)).is_none());
+        assert!(!scene.occluded(ray));
+    }
+
+    #[test]
+    fn test_degenerate_shapes() {
+        let geometry = || -> Vec<Box<dyn Shape<WorldSpace>>> {
+            vec![
+                Box::new(RectPrism::new(WVec3::splat(0.0), WVec3::splat(1.0))),
+                // corners on a line, so it has no normal
+                Box::new(shapes::Triangle::new(
+                    WPoint3::new(-1.0, 0.0, 0.0),
+                    WPoint3::new(-1.0, 1.0, 1.0),
+                    WPoint3::new(-1.0, 2.0, 2.0),
+                )),
+                Box::new(RectPrism::new(
+                    WVec3::new(3.0, 0.0, 0.0),
+                    WVec3::new(4.0, 1.0, 1.0),
+                )),
+            ]
+        };
+
+        let err = Scene::try_new(geometry()).unwrap_err();
+        assert_eq!(err.id, ShapeId(1));
+        assert!(err.shape.starts_with("Triangle"));
+        assert!(err.to_string().contains("line"));
+
+        // left out, without changing the ids of the shapes after it
+        let scene = Scene::new(geometry());
+        assert!(scene.shape(ShapeId(1)).is_none());
+        let ray = Ray::new(WPoint3::new(-2.0, 0.5, 0.5), WVec3::new(1.0, 0.0, 0.0));
+        assert_eq!(scene.intersects(ray).unwrap().shape_id, Some(ShapeId(0)));
+        let ray = Ray::new(WPoint3::new(2.0, 0.5, 0.5), WVec3::new(1.0, 0.0, 0.0));
+        assert_eq!(scene.intersects(ray).unwrap().shape_id, Some(ShapeId(2)));
+
+        // rays without a direction miss everything
+        for dir in [WVec3::zero(), WVec3::splat(f64::NAN)] {
+            let ray = Ray { dir, ..ray };
+            assert_eq!(scene.intersects(ray), None);
+            assert!(!scene.occluded(ray));
+        }
+
+        // shapes which become degenerate as they move are removed, and reported
+        let mut scene = Scene::new(geometry());
+        let degenerate = scene.rebucket(vec![
+            (ShapeId(0), geometry().remove(1)),
+            (ShapeId(2), geometry().remove(2)),
+        ]);
+        assert_eq!(degenerate.len(), 1);
+        assert_eq!(degenerate[0].id, ShapeId(0));
+        assert!(degenerate[0].shape.starts_with("Triangle"));
+        assert!(scene.shape(ShapeId(0)).is_none());
+        assert!(scene.shape(ShapeId(2)).is_some());
+    }
+
+    #[test]
+    fn test_pick() {
+        let scene = Scene::new(vec![
+            Box::new(RectPrism::tagged(
+                WVec3::new(-1.0, -1.0, -1.0),
+                WVec3::new(1.0, 1.0, 1.0),
+                7,
+            )),
+            Box::new(RectPrism::tagged(
+                WVec3::new(3.0, -1.0, -1.0),
+                WVec3::new(5.0, 1.0, 1.0),
+                8,
+            )),
+        ]);
+        let camera = Camera::look_at(
+            WPoint3::new(0.0, 0.0, 10.0),
+            WVec3::zero(),
+            WVec3::new(0.0, 1.0, 0.0),
+        )
+        .perspective(60.0, 200.0, 100.0, 0.1, 100.0);
+
+        let ray = camera.unproject(100.0, 50.0);
+        assert!(ray.dir.approx_eq(&WVec3::new(0.0, 0.0, -1.0)));
+        assert_eq!(ray.point, camera.eye());
+        assert!(ray.at(ray.tmin).approx_eq(&WPoint3::new(0.0, 0.0, 9.9)));
+        assert!((ray.tmin - 0.1).abs() < 1.0e-9);
+        assert!((ray.tmax - 100.0).abs() < 1.0e-9);
 
-        match self.intersects(r) {
-            Some(hitdata) => {
-                let diff = (hitdata.dist_to - v.length()).abs();
-                diff < 1.0e-1
+        // the distance to the hit is measured from the eye
+        let hit = scene.pick(&camera, 100.0, 50.0).unwrap();
+        assert!(hit.hit_point.approx_eq(&WPoint3::new(0.0, 0.0, 1.0)));
+        assert!((hit.dist_to - 9.0).abs() < 1.0e-9);
+        assert_eq!(hit.tag, 7);
+        assert_eq!(hit.shape_id, Some(ShapeId(0)));
+
+        // to the right of the center lies the second prism
+        let hit = scene.pick(&camera, 140.0, 50.0).unwrap();
+        assert_eq!(hit.tag, 8);
+        assert!(scene.shape(hit.shape_id.unwrap()).is_some());
+
+        assert_eq!(scene.pick(&camera, 100.0, 95.0), None);
+    }
+
+    #[test]
+    fn test_project() {
+        let camera = Camera::look_at(
+            WPoint3::new(0.0, 0.0, 10.0),
+            WVec3::zero(),
+            WVec3::new(0.0, 1.0, 0.0),
+        )
+        .perspective(60.0, 200.0, 100.0, 0.1, 100.0);
+
+        let projected = camera.project(WPoint3::origin()).unwrap();
+        assert!((projected.x - 100.0).abs() < 1.0e-9);
+        assert!((projected.y - 50.0).abs() < 1.0e-9);
+        assert!(projected.z > 0.0 && projected.z < 1.0);
+
+        // projecting and unprojecting a point leads back to it
+        let point = WPoint3::new(2.0, -1.5, 3.0);
+        let projected = camera.project(point).unwrap();
+        let ray = camera.unproject(projected.x, projected.y);
+        let back = ray.at((point - ray.point).dot(ray.dir));
+        assert!(back.approx_eq_eps(&point, &WPoint3::splat(1.0e-6)));
+
+        assert!(camera.in_frustum(point));
+        assert!(!camera.in_frustum(WPoint3::new(0.0, 0.0, 20.0)));
+        assert!(!camera.in_frustum(WPoint3::new(0.0, 0.0, -95.0)));
+        assert!(!camera.in_frustum(WPoint3::new(15.0, 0.0, 0.0)));
+        assert_eq!(camera.project(WPoint3::new(0.0, 0.0, 20.0)), None);
+
+        assert_eq!(camera.eye(), WPoint3::new(0.0, 0.0, 10.0));
+        assert_eq!(camera.fovy(), Some(60.0));
+        assert_eq!((camera.znear(), camera.zfar()), (0.1, 100.0));
+    }
+
+    #[test]
+    fn test_clipping() {
+        let camera = Camera::look_at(
+            WPoint3::new(0.0, 0.0, 10.0),
+            WVec3::zero(),
+            WVec3::new(0.0, 1.0, 0.0),
+        )
+        .perspective(60.0, 200.0, 100.0, 0.1, 100.0);
+        let on_canvas = |segments: &[LineSegment<CameraSpace>]| {
+            segments.iter().all(|s| {
+                [s.p1, s.p2].iter().all(|p| {
+                    (-1.0e-9..=200.0 + 1.0e-9).contains(&p.x)
+                        && (-1.0e-9..=100.0 + 1.0e-9).contains(&p.y)
+                })
+            })
+        };
+
+        // a segment passing behind the eye is cut off at the near plane
+        let segment = LineSegment::new(WPoint3::new(0.01, 0.0, 0.0), WPoint3::new(0.01, 0.0, 20.0));
+        let clipped = camera.clip_segment(&segment);
+        assert_eq!(clipped.len(), 1);
+        assert!((clipped[0].p1.z - 0.0).abs() < 1.0e-9);
+        assert!((clipped[0].p2.z - 9.9).abs() < 1.0e-9);
+        let projected = camera.project_segment(&segment);
+        assert_eq!(projected.len(), 1);
+        assert!(on_canvas(&projected));
+
+        // a segment crossing the whole field of view is cut off at the edges of the canvas
+        let segment = LineSegment::new(
+            WPoint3::new(-100.0, 0.0, 0.0),
+            WPoint3::new(100.0, 0.0, 0.0),
+        );
+        let projected = camera.project_segment(&segment);
+        assert_eq!(projected.len(), 1);
+        let (x1, x2) = (projected[0].p1.x, projected[0].p2.x);
+        assert!(x1.abs() < 1.0e-6 && (x2 - 200.0).abs() < 1.0e-6);
+        assert!((projected[0].p1.y - 50.0).abs() < 1.0e-6);
+
+        // segments off to the side, behind the eye and beyond the far plane are left out
+        for (p1, p2) in [
+            (WPoint3::new(50.0, 0.0, 0.0), WPoint3::new(60.0, 0.0, 0.0)),
+            (WPoint3::new(-1.0, 0.0, 15.0), WPoint3::new(1.0, 0.0, 15.0)),
+            (
+                WPoint3::new(-1.0, 0.0, -95.0),
+                WPoint3::new(1.0, 0.0, -95.0),
+            ),
+        ] {
+            assert!(camera.clip_segment(&LineSegment::new(p1, p2)).is_empty());
+        }
+
+        // curvilinear projections split segments passing through the near plane around the eye
+        let fisheye = Camera::look_at(
+            WPoint3::origin(),
+            WVec3::new(0.0, 0.0, -1.0),
+            WVec3::new(0.0, 1.0, 0.0),
+        )
+        .with_projection(Projection::Fisheye { fov: 180.0 }, 200.0, 100.0, 0.1, 100.0);
+        let segment = LineSegment::new(WPoint3::new(-200.0, 0.0, 0.0), WPoint3::new(5.0, 0.0, 0.0));
+        let clipped = fisheye.clip_segment(&segment);
+        assert_eq!(clipped.len(), 2);
+        assert!(clipped[0].p1.approx_eq(&WPoint3::new(-100.0, 0.0, 0.0)));
+        assert!(clipped[0].p2.approx_eq(&WPoint3::new(-0.1, 0.0, 0.0)));
+        assert!(clipped[1].p1.approx_eq(&WPoint3::new(0.1, 0.0, 0.0)));
+        assert!(clipped[1].p2.approx_eq(&WPoint3::new(5.0, 0.0, 0.0)));
+        assert!(on_canvas(&fisheye.project_segment(&segment)));
+
+        // a floor stretching out behind the camera renders within the canvas
+        let scene = Scene::new(vec![Box::new(RectPrism::new(
+            WVec3::new(-50.0, -2.0, -50.0),
+            WVec3::new(50.0, -1.0, 50.0),
+        ))]);
+        let rendered = scene.attach_camera(camera).render();
+        assert!(!rendered.is_empty());
+        assert!(on_canvas(&rendered));
+    }
+
+    #[test]
+    fn test_chop_segment() {
+        let camera = Camera::look_at(
+            WPoint3::new(0.0, 0.0, 10.0),
+            WVec3::zero(),
+            WVec3::new(0.0, 1.0, 0.0),
+        )
+        .perspective(60.0, 200.0, 100.0, 0.1, 100.0);
+        let canvas_length = |camera: &Camera, chunk: &LineSegment<WorldSpace>| {
+            let (c1, c2) = (camera.project(chunk.p1), camera.project(chunk.p2));
+            (c2.unwrap().to_2d() - c1.unwrap().to_2d()).length()
+        };
+
+        // a segment receding into the distance is chopped into chunks of the same length on the
+        // canvas, which are shorter in the world the closer they are
+        let segment =
+            LineSegment::new(WPoint3::new(0.0, -1.0, 5.0), WPoint3::new(0.0, -1.0, -80.0));
+        for size in [1.0, 4.0] {
+            let camera = camera.with_chunk_size(size);
+            assert_eq!(camera.chunk_size(), size);
+            let chunks = camera.chop_segment(&segment);
+            assert!(chunks.len() > 1);
+            let first = canvas_length(&camera, &chunks[0]);
+            assert!((first - size).abs() < size / 2.0);
+            for chunk in &chunks {
+                assert!((canvas_length(&camera, chunk) - first).abs() < 1.0e-9);
             }
-            None => true,
+            let world_length = |chunk: &LineSegment<WorldSpace>| (chunk.p2 - chunk.p1).length();
+            assert!(world_length(&chunks[0]) * 10.0 < world_length(chunks.last().unwrap()));
+            assert!(chunks[0].p1.approx_eq(&segment.p1));
+            assert!(chunks.last().unwrap().p2.approx_eq(&segment.p2));
         }
+
+        // segments shorter than a chunk are left whole
+        let short = LineSegment::new(WPoint3::new(0.0, 0.0, 0.0), WPoint3::new(0.01, 0.0, 0.0));
+        assert_eq!(camera.chop_segment(&short).len(), 1);
+
+        // chunks which aren't a positive, finite number of pixels long are left at the default
+        let chunks = camera.chop_segment(&segment).len();
+        for size in [0.0, -4.0, f64::NAN, f64::INFINITY] {
+            let camera = camera.with_chunk_size(4.0).with_chunk_size(size);
+            assert_eq!(camera.chunk_size(), DEFAULT_CHUNK_SIZE);
+            assert_eq!(camera.chop_segment(&segment).len(), chunks);
+        }
+
+        // curvilinear projections chop segments into chunks covering the same angle
+        let fisheye = Camera::look_at(
+            WPoint3::origin(),
+            WVec3::new(0.0, 0.0, -1.0),
+            WVec3::new(0.0, 1.0, 0.0),
+        )
+        .with_projection(Projection::Fisheye { fov: 180.0 }, 200.0, 100.0, 0.1, 100.0)
+        .with_chunk_size(2.0);
+        let segment =
+            LineSegment::new(WPoint3::new(-10.0, 1.0, -1.0), WPoint3::new(3.0, 1.0, -1.0));
+        let chunks = fisheye.chop_segment(&segment);
+        let angle = |chunk: &LineSegment<WorldSpace>| {
+            let (o1, o2) = (chunk.p1.to_vector(), chunk.p2.to_vector());
+            o1.cross(o2).length().atan2(o1.dot(o2))
+        };
+        let pixel = std::f64::consts::PI / 100.0;
+        for chunk in &chunks {
+            assert!((angle(chunk) - angle(&chunks[0])).abs() < 1.0e-9);
+            assert!((angle(chunk) / pixel - 2.0).abs() < 1.0);
+        }
+        assert!(chunks.last().unwrap().p2.approx_eq(&segment.p2));
+    }
+
+    #[test]
+    fn test_boundary_tolerance() {
+        // a wall, the top front edge of which is partly hidden behind a box
+        let scene = Scene::new(vec![
+            Box::new(RectPrism::new(
+                WVec3::new(-1.0, -1.0, -1.0),
+                WVec3::new(1.0, 1.0, 1.0),
+            )),
+            Box::new(RectPrism::new(
+                WVec3::new(-5.0, -1.0, -6.0),
+                WVec3::new(5.0, 1.0, -5.0),
+            )),
+        ]);
+        let camera = Camera::look_at(
+            WPoint3::new(0.0, 0.0, 10.0),
+            WVec3::zero(),
+            WVec3::new(0.0, 1.0, 0.0),
+        )
+        .perspective(60.0, 200.0, 100.0, 0.1, 100.0)
+        .with_chunk_size(7.0);
+        assert_eq!(camera.boundary_tolerance(), None);
+
+        // the edge is hidden where lines of sight to it pass the box's front corners
+        let edge = camera.project(WPoint3::new(0.0, 1.0, -5.0)).unwrap().y;
+        let corner = camera.project(WPoint3::new(1.0, 1.0, 1.0)).unwrap().x - 100.0;
+        let ends = |camera: Camera| {
+            let mut ends: Vec<f64> = scene
+                .attach_camera(camera)
+                .render()
+                .iter()
+                .filter(|s| (s.p1.y - edge).abs() < 1.0e-6 && (s.p2.y - edge).abs() < 1.0e-6)
+                .flat_map(|s| [s.p1.x, s.p2.x])
+                .collect();
+            ends.sort_by(f64::total_cmp);
+            ends
+        };
+        let far = camera.project(WPoint3::new(5.0, 1.0, -5.0)).unwrap().x - 100.0;
+        let expected = [100.0 - far, 100.0 - corner, 100.0 + corner, 100.0 + far];
+
+        let refined = ends(camera.with_boundary_tolerance(0.01));
+        assert_eq!(refined.len(), 4);
+        for (end, expected) in refined.iter().zip(expected) {
+            assert!((end - expected).abs() < 0.01);
+        }
+
+        // without looking for the boundaries, the edge is only hidden in whole chunks
+        let chunked = ends(camera);
+        assert_eq!(chunked.len(), 4);
+        assert!((chunked[1] - expected[1]).abs() > 0.1 || (chunked[2] - expected[2]).abs() > 0.1);
+    }
+
+    #[test]
+    fn test_orthographic() {
+        let look = Camera::look_at(
+            WPoint3::new(0.0, 0.0, 10.0),
+            WVec3::zero(),
+            WVec3::new(0.0, 1.0, 0.0),
+        );
+        let camera = look.orthographic(20.0, 10.0, 200.0, 100.0, 0.1, 100.0);
+
+        // things appear the same size however far away they are
+        for z in [5.0, 0.0, -50.0] {
+            let projected = camera.project(WPoint3::new(5.0, 2.5, z)).unwrap();
+            assert!((projected.x - 150.0).abs() < 1.0e-9);
+            assert!((projected.y - 75.0).abs() < 1.0e-9);
+        }
+        assert_eq!(camera.distance_to(WPoint3::new(5.0, 2.5, 0.0)), 10.0);
+        assert_eq!(camera.fovy(), None);
+
+        // rays start level with the eye
+        let ray = camera.unproject(150.0, 75.0);
+        assert!(ray.dir.approx_eq(&WVec3::new(0.0, 0.0, -1.0)));
+        assert!(ray.point.approx_eq(&WPoint3::new(5.0, 2.5, 10.0)));
+        assert!(ray.at(ray.tmin).approx_eq(&WPoint3::new(5.0, 2.5, 9.9)));
+        assert!(ray.at(ray.tmax).approx_eq(&WPoint3::new(5.0, 2.5, -90.0)));
+        assert_eq!(
+            camera.viewpoint(WPoint3::new(5.0, 2.5, 0.0)),
+            WPoint3::new(5.0, 2.5, 9.9)
+        );
+
+        // a strip on the ground, hidden right under a thin slab when looking straight down, but
+        // not from the eye of a perspective projection
+        let slab = || -> Box<dyn Shape<WorldSpace>> {
+            Box::new(RectPrism::new(
+                WVec3::new(3.0, -1.0, 5.0),
+                WVec3::new(5.0, 1.0, 5.2),
+            ))
+        };
+        let strip = Box::new(RectPrism::new(
+            WVec3::new(3.5, -0.5, 0.0),
+            WVec3::new(4.5, 0.5, 0.1),
+        ));
+        let alone = Scene::new(vec![slab()]);
+        let both = Scene::new(vec![slab(), strip]);
+
+        let render = |scene: &Scene, camera| scene.attach_camera(camera).render().len();
+        assert_eq!(render(&both, camera), render(&alone, camera));
+        let camera = Camera::look_at(
+            WPoint3::new(0.0, 0.0, 10.0),
+            WVec3::zero(),
+            WVec3::new(0.0, 1.0, 0.0),
+        )
+        .perspective(60.0, 200.0, 100.0, 0.1, 100.0);
+        assert!(render(&both, camera) > render(&alone, camera));
+    }
+
+    #[test]
+    fn test_axonometric() {
+        // the lengths of the world axes on the canvas, and the angles between them
+        let axes = |axonometry| {
+            let camera =
+                Camera::axonometric(WVec3::zero(), WVec3::new(0.0, 0.0, 1.0), axonometry, 10.0)
+                    .orthographic(10.0, 10.0, 100.0, 100.0, 0.1, 100.0);
+            let origin = camera.project(WPoint3::origin()).unwrap();
+            let axes: Vec<_> = [(1.0, 0.0, 0.0), (0.0, 1.0, 0.0), (0.0, 0.0, 1.0)]
+                .iter()
+                .map(|&(x, y, z)| {
+                    let end = camera.project(WPoint3::new(x, y, z)).unwrap();
+                    (end - origin).to_2d()
+                })
+                .collect();
+            let lengths: Vec<_> = axes.iter().map(|axis| axis.length()).collect();
+            let angles: Vec<_> = (0..3)
+                .map(|i| {
+                    let (a, b) = (axes[i], axes[(i + 1) % 3]);
+                    a.cross(b).atan2(a.dot(b)).abs().to_degrees()
+                })
+                .collect();
+            (lengths, angles)
+        };
+        let close = |a: f64, b: f64| (a - b).abs() < 1.0e-9;
+
+        let (lengths, angles) = axes(Axonometry::Isometric);
+        assert!(lengths.iter().all(|&length| close(length, lengths[0])));
+        assert!(
+            angles.iter().all(|&angle| close(angle, 120.0)),
+            "{:?}",
+            angles
+        );
+
+        let (lengths, _) = axes(Axonometry::Dimetric { elevation: 30.0 });
+        assert!(close(lengths[0], lengths[1]));
+        assert!(!close(lengths[0], lengths[2]));
+
+        let (lengths, _) = axes(Axonometry::Trimetric {
+            azimuth: 20.0,
+            elevation: 30.0,
+        });
+        assert!(!close(lengths[0], lengths[1]));
+        assert!(!close(lengths[1], lengths[2]));
+        assert!(!close(lengths[2], lengths[0]));
+    }
+
+    #[test]
+    fn test_lens_shift() {
+        let look = || {
+            Camera::look_at(
+                WPoint3::new(0.0, 0.0, 10.0),
+                WVec3::zero(),
+                WVec3::new(0.0, 1.0, 0.0),
+            )
+        };
+        let projection = Projection::Perspective {
+            fovy: 60.0,
+            shift_x: 0.25,
+            shift_y: 0.5,
+        };
+        let camera = look().with_projection(projection, 200.0, 100.0, 0.1, 100.0);
+        assert_eq!(camera.fovy(), Some(60.0));
+
+        // what the camera looks at moves off center, but the camera doesn't turn
+        let projected = camera.project(WPoint3::origin()).unwrap();
+        assert!((projected.x - 50.0).abs() < 1.0e-9);
+        assert!(projected.y.abs() < 1.0e-9);
+
+        // the middle of the canvas shows what lies a quarter of the canvas to the right and half
+        // of it up without the shift
+        let unshifted = look().perspective(60.0, 200.0, 100.0, 0.1, 100.0);
+        let point = camera.unproject(100.0, 50.0).at(5.0);
+        let projected = unshifted.project(point).unwrap();
+        assert!((projected.x - 150.0).abs() < 1.0e-9);
+        assert!((projected.y - 100.0).abs() < 1.0e-9);
+    }
+
+    #[test]
+    fn test_two_point_perspective() {
+        let look = || {
+            Camera::look_at(
+                WPoint3::new(10.0, 3.0, 1.5),
+                WVec3::new(0.0, 0.0, 5.0),
+                WVec3::new(0.0, 0.0, 1.0),
+            )
+        };
+        let camera = look().two_point_perspective(60.0, 200.0, 100.0, 0.1, 100.0);
+        assert!(camera.forward().z.abs() < 1.0e-12);
+
+        let projected = camera.project(WPoint3::new(0.0, 0.0, 5.0)).unwrap();
+        assert!((projected.x - 100.0).abs() < 1.0e-9);
+        assert!((projected.y - 50.0).abs() < 1.0e-9);
+
+        // verticals stay vertical, unlike when the camera is tilted up
+        let vertical = |camera: &Camera| {
+            let bottom = camera.project(WPoint3::new(2.0, -1.0, 0.0)).unwrap();
+            let top = camera.project(WPoint3::new(2.0, -1.0, 8.0)).unwrap();
+            (top.x - bottom.x).abs() < 1.0e-9
+        };
+        assert!(vertical(&camera));
+        assert!(!vertical(
+            &look().perspective(60.0, 200.0, 100.0, 0.1, 100.0)
+        ));
+    }
+
+    #[test]
+    fn test_curvilinear() {
+        let camera = |projection| {
+            Camera::look_at(
+                WPoint3::origin(),
+                WVec3::new(0.0, 0.0, -1.0),
+                WVec3::new(0.0, 1.0, 0.0),
+            )
+            .with_projection(projection, 200.0, 100.0, 0.1, 100.0)
+        };
+        let fisheye = camera(Projection::Fisheye { fov: 180.0 });
+        let stereographic = camera(Projection::Stereographic { fov: 180.0 });
+        let panorama = camera(Projection::Equirectangular {
+            fovx: 360.0,
+            fovy: 180.0,
+        });
+        let close =
+            |a: CPoint3, x: f64, y: f64| (a.x - x).abs() < 1.0e-9 && (a.y - y).abs() < 1.0e-9;
+        let at = |camera: &Camera, x, y, z| camera.project(WPoint3::new(x, y, z)).unwrap();
+
+        for camera in [&fisheye, &stereographic, &panorama] {
+            assert!(close(at(camera, 0.0, 0.0, -5.0), 100.0, 50.0));
+            assert!(close(at(camera, 5.0, 0.0, 0.0), 150.0, 50.0));
+            assert!((at(camera, 0.0, 0.0, -5.0).z - 4.9 / 99.9).abs() < 1.0e-9);
+
+            // unprojecting leads back to the same point of the canvas
+            for (x, y) in [(100.0, 50.0), (130.0, 20.0), (60.0, 90.0)] {
+                let ray = camera.unproject(x, y);
+                assert!(ray
+                    .at(ray.tmin)
+                    .approx_eq(&(WPoint3::origin() + ray.dir * 0.1)));
+                assert!(close(camera.project(ray.at(5.0)).unwrap(), x, y));
+            }
+        }
+        // halfway up, and closer to the middle than with a fisheye at 45 degrees
+        assert!(close(at(&panorama, 0.0, 5.0, -5.0), 100.0, 75.0));
+        let radius = 50.0 * (std::f64::consts::PI / 8.0).tan();
+        assert!(close(
+            at(&stereographic, 5.0, 0.0, -5.0),
+            100.0 + radius,
+            50.0
+        ));
+        assert!(close(at(&fisheye, 5.0, 0.0, -5.0), 125.0, 50.0));
+        assert!(panorama.in_frustum(WPoint3::new(0.0, -3.0, 5.0)));
+        assert!(!fisheye.in_frustum(WPoint3::new(0.0, -3.0, 5.0)));
+
+        // straight lines bend, but the pieces they're split into stay on the curve
+        let segment = LineSegment::new(WPoint3::new(-5.0, 2.0, -5.0), WPoint3::new(5.0, 2.0, -5.0));
+        let curve = fisheye.project_segment(&segment);
+        assert!(curve.len() > 4);
+        assert!(curve.windows(2).all(|pair| pair[0].p2 == pair[1].p1));
+        let top = at(&fisheye, 0.0, 2.0, -5.0);
+        assert!(curve.iter().any(|piece| piece.p1 == top || piece.p2 == top));
+        assert!(curve.iter().all(|piece| piece.p1.y < top.y + 1.0e-9));
+
+        // crossing the seam of the panorama, right behind the eye
+        let segment = LineSegment::new(WPoint3::new(-1.0, 0.0, 5.0), WPoint3::new(1.0, 0.0, 5.0));
+        let pieces = panorama.project_segment(&segment);
+        assert!(pieces.iter().any(|piece| piece.p1.x < 10.0));
+        assert!(pieces.iter().any(|piece| piece.p1.x > 190.0));
+        assert!(pieces
+            .iter()
+            .all(|piece| (piece.p2 - piece.p1).length() < 10.0));
+
+        // everything around the eye is drawn, with visibility tested along the eye's rays
+        let scene = Scene::new(vec![
+            Box::new(RectPrism::new(
+                WVec3::new(-1.0, -1.0, -6.0),
+                WVec3::new(1.0, 1.0, -4.0),
+            )),
+            Box::new(RectPrism::new(
+                WVec3::new(-1.0, -1.0, 4.0),
+                WVec3::new(1.0, 1.0, 6.0),
+            )),
+        ]);
+        let paths = scene.attach_camera(panorama).render();
+        let ahead = paths.iter().filter(|path| (path.p1.x - 100.0).abs() < 20.0);
+        let behind = paths
+            .iter()
+            .filter(|path| path.p1.x < 20.0 || path.p1.x > 180.0);
+        assert!(ahead.count() > 0 && behind.count() > 0);
+        assert!(paths
+            .iter()
+            .all(|path| path.p1.x >= 0.0 && path.p1.x <= 200.0));
+    }
+
+    #[test]
+    fn test_oblique() {
+        let camera = Camera::look_at(
+            WPoint3::new(0.0, 0.0, 10.0),
+            WVec3::zero(),
+            WVec3::new(0.0, 1.0, 0.0),
+        )
+        .with_projection(
+            Projection::cabinet(20.0, 10.0, 45.0),
+            200.0,
+            100.0,
+            0.1,
+            100.0,
+        );
+        let close =
+            |a: CPoint3, x: f64, y: f64| (a.x - x).abs() < 1.0e-9 && (a.y - y).abs() < 1.0e-9;
+
+        // the plane through the center keeps its shape, and depth recedes up and to the right
+        assert!(close(
+            camera.project(WPoint3::origin()).unwrap(),
+            100.0,
+            50.0
+        ));
+        assert!(close(
+            camera.project(WPoint3::new(5.0, 2.5, 0.0)).unwrap(),
+            150.0,
+            75.0
+        ));
+        let offset = 2.0 * 0.5 * std::f64::consts::FRAC_1_SQRT_2 * 10.0;
+        let behind = camera.project(WPoint3::new(0.0, 0.0, -2.0)).unwrap();
+        assert!(close(behind, 100.0 + offset, 50.0 + offset));
+
+        // lines of sight slant with the receding lines
+        let point = WPoint3::new(1.0, -1.0, -2.0);
+        let viewpoint = camera.viewpoint(point);
+        assert!((viewpoint.z - 9.9).abs() < 1.0e-9);
+        let (a, b) = (
+            camera.project(point).unwrap(),
+            camera.project(viewpoint).unwrap(),
+        );
+        assert!(close(a, b.x, b.y));
+
+        let ray = camera.unproject(a.x, a.y);
+        assert!(ray.dir.approx_eq(&(point - viewpoint).normalize()));
+
+        // hidden behind a box which lies along the slanted line of sight, though straight above
+        // it lies nothing at all
+        let d = 0.5 * std::f64::consts::FRAC_1_SQRT_2 * 5.0;
+        let scene = Scene::new(vec![Box::new(RectPrism::new(
+            WVec3::new(d - 0.5, d - 0.5, 4.5),
+            WVec3::new(d + 0.5, d + 0.5, 5.5),
+        ))]);
+        let point = WPoint3::origin();
+        assert!(!scene.visible(camera.viewpoint(point), point));
+        assert!(scene.visible(WPoint3::new(0.0, 0.0, 9.9), point));
     }
 }
@@ -0,0 +1,207 @@
+use super::util::{
+    closest_hit, cylinder_silhouette, ray_cylinder, ray_sphere, sphere_normals_to_paths,
+    sphere_silhouette,
+};
+use crate::path::LineSegment;
+use crate::{HitData, Ray, Shape, WPoint3, WVec3, WorldSpace};
+
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(test, derive(PartialEq))]
+/// A sphere swept along a line segment: a cylinder capped with a hemisphere at each end.
+pub struct Capsule {
+    /// The center of the first hemispherical cap.
+    pub p1: WPoint3,
+    /// The center of the second hemispherical cap.
+    pub p2: WPoint3,
+    /// The radius of the swept sphere.
+    pub radius: f64,
+    pub tag: usize,
+}
+
+impl Capsule {
+    pub fn new(p1: WPoint3, p2: WPoint3, radius: f64) -> Capsule {
+        Self::tagged(p1, p2, radius, 0)
+    }
+
+    pub fn tagged(p1: WPoint3, p2: WPoint3, radius: f64, tag: usize) -> Capsule {
+        Capsule {
+            p1,
+            p2,
+            radius,
+            tag,
+        }
+    }
+
+    /// The normalized direction from `p1` to `p2`, and the distance between them.
+    fn axis(&self) -> (WVec3, f64) {
+        let diff = self.p2 - self.p1;
+        let length = diff.length();
+        if length == 0.0 {
+            (WVec3::new(1.0, 0.0, 0.0), 0.0)
+        } else {
+            (diff / length, length)
+        }
+    }
+}
+
+impl Shape<WorldSpace> for Capsule {
+    fn hit_by(&self, ray: &Ray) -> Option<HitData> {
+        let (axis, length) = self.axis();
+        let at = |t: f64| ray.point + ray.dir * t;
+
+        let mut candidates = Vec::new();
+
+        if length > 0.0 {
+            if let Some((t0, t1)) = ray_cylinder(ray, self.p1, axis, self.radius) {
+                for t in [t0, t1] {
+                    let offset = at(t) - self.p1;
+                    let along = offset.dot(axis);
+                    if (0.0..=length).contains(&along) {
+                        candidates.push((t, (offset - axis * along) / self.radius));
+                    }
+                }
+            }
+        }
+
+        if let Some((t0, t1)) = ray_sphere(ray, self.p1, self.radius) {
+            for t in [t0, t1] {
+                let offset = at(t) - self.p1;
+                if offset.dot(axis) <= 0.0 || length == 0.0 {
+                    candidates.push((t, offset / self.radius));
+                }
+            }
+        }
+
+        if let Some((t0, t1)) = ray_sphere(ray, self.p2, self.radius) {
+            for t in [t0, t1] {
+                let offset = at(t) - self.p2;
+                if offset.dot(axis) >= 0.0 {
+                    candidates.push((t, offset / self.radius));
+                }
+            }
+        }
+
+        closest_hit(candidates).map(|(t, normal)| {
+            HitData::new(at(t), t)
+                .with_normal(normal)
+                .with_tag(self.tag)
+        })
+    }
+
+    fn paths(&self) -> Vec<LineSegment<WorldSpace>> {
+        // A capsule is smooth everywhere, so its outline is entirely made up of silhouettes.
+        Vec::new()
+    }
+
+    fn silhouette_paths(&self, eye: WPoint3) -> Vec<LineSegment<WorldSpace>> {
+        let (axis, length) = self.axis();
+        let mut paths = Vec::new();
+
+        if length > 0.0 {
+            for normal in cylinder_silhouette(self.p1, axis, self.radius, eye) {
+                let offset = normal * self.radius;
+                paths.push(LineSegment::tagged(
+                    self.p1 + offset,
+                    self.p2 + offset,
+                    self.tag,
+                ));
+            }
+        }
+
+        let normals = sphere_silhouette(self.p1, self.radius, eye);
+        paths.extend(sphere_normals_to_paths(
+            self.p1,
+            self.radius,
+            &normals,
+            |n| length == 0.0 || n.dot(axis) <= 0.0,
+            self.tag,
+        ));
+
+        if length > 0.0 {
+            let normals = sphere_silhouette(self.p2, self.radius, eye);
+            paths.extend(sphere_normals_to_paths(
+                self.p2,
+                self.radius,
+                &normals,
+                |n| n.dot(axis) >= 0.0,
+                self.tag,
+            ));
+        }
+
+        paths
+    }
+
+    fn bounding_box(&self) -> Option<crate::AABB<crate::WorldSpace>> {
+        let r = WVec3::splat(self.radius);
+        let min = self.p1.min(self.p2) - r;
+        let max = self.p1.max(self.p2) + r;
+        Some(crate::AABB::new(min, max))
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.radius > 0.0 {
+            Ok(())
+        } else {
+            Err(format!("its radius of {} isn't positive", self.radius))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_capsule_hit_by() {
+        let capsule = Capsule::new(
+            WPoint3::new(0.0, 0.0, 0.0),
+            WPoint3::new(0.0, 0.0, 2.0),
+            0.5,
+        );
+
+        // cylindrical body
+        assert_eq!(
+            capsule.hit_by(&Ray::new(
+                WPoint3::new(-2.0, 0.0, 1.0),
+                WVec3::new(1.0, 0.0, 0.0)
+            )),
+            Some(HitData::new(WPoint3::new(-0.5, 0.0, 1.0), 1.5))
+        );
+
+        // bottom cap
+        assert_eq!(
+            capsule.hit_by(&Ray::new(
+                WPoint3::new(0.0, 0.0, -2.0),
+                WVec3::new(0.0, 0.0, 1.0)
+            )),
+            Some(HitData::new(WPoint3::new(0.0, 0.0, -0.5), 1.5))
+        );
+
+        // top cap, from the inside
+        assert_eq!(
+            capsule.hit_by(&Ray::new(
+                WPoint3::new(0.0, 0.0, 1.0),
+                WVec3::new(0.0, 0.0, 1.0)
+            )),
+            Some(HitData::new(WPoint3::new(0.0, 0.0, 2.5), 1.5))
+        );
+
+        // past the end of the top cap
+        assert_eq!(
+            capsule.hit_by(&Ray::new(
+                WPoint3::new(-2.0, 0.0, 2.6),
+                WVec3::new(1.0, 0.0, 0.0)
+            )),
+            None
+        );
+
+        // misses the body
+        assert_eq!(
+            capsule.hit_by(&Ray::new(
+                WPoint3::new(-2.0, 0.6, 1.0),
+                WVec3::new(1.0, 0.0, 0.0)
+            )),
+            None
+        );
+    }
+}
@@ -0,0 +1,773 @@
+use std::f64::consts::{PI, TAU};
+
+use super::util::{closest_hit, cylinder_silhouette, orthonormal_basis, ray_cylinder};
+use crate::path::LineSegment;
+use crate::{HitData, Ray, Shape, WPoint3, WVec3, WorldSpace, AABB};
+
+/// How many points are used to trace one flank of a tooth.
+const FLANK_SAMPLES: usize = 12;
+/// How many points are used to trace arcs of the root and tip circles, per tooth.
+const ARC_SAMPLES: usize = 6;
+
+#[derive(Debug, Clone)]
+/// A gear: a toothed profile extruded along an axis, with a round bore through its middle.
+///
+/// The profile is traced counterclockwise around the axis and must be star-shaped, i.e. every
+/// ray from the axis crosses its outline once. Both spur gears and sprockets are.
+pub struct Gear {
+    /// The point on the axis halfway through the gear's face.
+    pub center: WPoint3,
+    axis: WVec3,
+    u: WVec3,
+    v: WVec3,
+    teeth: usize,
+    face_width: f64,
+    bore_radius: f64,
+    outer_radius: f64,
+    /// Corners of the profile in the plane of `u` and `v`, in order of increasing angle.
+    profile: Vec<[f64; 2]>,
+    /// The angle of each profile corner around the axis, unwrapped so that it only increases.
+    angles: Vec<f64>,
+    pub tag: usize,
+}
+
+impl Gear {
+    /// Creates a spur gear with involute teeth.
+    ///
+    /// `module` is the pitch diameter divided by the number of teeth, and `pressure_angle` is
+    /// given in degrees. The teeth have standard proportions: an addendum of one module and a
+    /// dedendum of one and a quarter modules.
+    pub fn spur(
+        center: WPoint3,
+        axis: WVec3,
+        module: f64,
+        teeth: usize,
+        pressure_angle: f64,
+        face_width: f64,
+        bore: f64,
+    ) -> Gear {
+        Self::spur_tagged(
+            center,
+            axis,
+            module,
+            teeth,
+            pressure_angle,
+            face_width,
+            bore,
+            0,
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn spur_tagged(
+        center: WPoint3,
+        axis: WVec3,
+        module: f64,
+        teeth: usize,
+        pressure_angle: f64,
+        face_width: f64,
+        bore: f64,
+        tag: usize,
+    ) -> Gear {
+        let profile = spur_profile(module, teeth, pressure_angle.to_radians());
+        Self::from_profile(center, axis, profile, teeth, face_width, bore / 2.0, tag)
+    }
+
+    /// Creates a sprocket for a roller chain of the given pitch and roller diameter.
+    pub fn sprocket(
+        center: WPoint3,
+        axis: WVec3,
+        pitch: f64,
+        roller_diameter: f64,
+        teeth: usize,
+        face_width: f64,
+        bore: f64,
+    ) -> Gear {
+        Self::sprocket_tagged(
+            center,
+            axis,
+            pitch,
+            roller_diameter,
+            teeth,
+            face_width,
+            bore,
+            0,
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn sprocket_tagged(
+        center: WPoint3,
+        axis: WVec3,
+        pitch: f64,
+        roller_diameter: f64,
+        teeth: usize,
+        face_width: f64,
+        bore: f64,
+        tag: usize,
+    ) -> Gear {
+        let profile = sprocket_profile(pitch, roller_diameter, teeth);
+        Self::from_profile(center, axis, profile, teeth, face_width, bore / 2.0, tag)
+    }
+
+    fn from_profile(
+        center: WPoint3,
+        axis: WVec3,
+        profile: Vec<[f64; 2]>,
+        teeth: usize,
+        face_width: f64,
+        bore_radius: f64,
+        tag: usize,
+    ) -> Gear {
+        let axis = axis.normalize();
+        let (u, v) = orthonormal_basis(axis);
+
+        let mut angles: Vec<f64> = Vec::with_capacity(profile.len());
+        for p in &profile {
+            let mut angle = p[1].atan2(p[0]);
+            if let Some(prev) = angles.last() {
+                while angle < *prev - 1.0e-9 {
+                    angle += TAU;
+                }
+            }
+            angles.push(angle);
+        }
+        let outer_radius = profile.iter().map(|p| p[0].hypot(p[1])).fold(0.0, f64::max);
+
+        Gear {
+            center,
+            axis,
+            u,
+            v,
+            teeth,
+            face_width,
+            bore_radius,
+            outer_radius,
+            profile,
+            angles,
+            tag,
+        }
+    }
+
+    fn to_local(&self, p: WPoint3) -> [f64; 3] {
+        let d = p - self.center;
+        [d.dot(self.u), d.dot(self.v), d.dot(self.axis)]
+    }
+
+    fn to_world(&self, x: f64, y: f64, z: f64) -> WPoint3 {
+        self.center + self.u * x + self.v * y + self.axis * z
+    }
+
+    fn to_world_vec(&self, x: f64, y: f64, z: f64) -> WVec3 {
+        self.u * x + self.v * y + self.axis * z
+    }
+
+    /// The index of the profile edge which spans the given angle around the axis.
+    fn edge_at(&self, angle: f64) -> usize {
+        let start = self.angles[0];
+        let angle = start + (angle - start).rem_euclid(TAU);
+        let ndx = self.angles.partition_point(|a| *a <= angle);
+        (ndx + self.profile.len() - 1) % self.profile.len()
+    }
+
+    fn edge(&self, ndx: usize) -> ([f64; 2], [f64; 2]) {
+        (
+            self.profile[ndx],
+            self.profile[(ndx + 1) % self.profile.len()],
+        )
+    }
+
+    /// The distance from the axis to the outline of the profile, in the direction of `p`.
+    fn radius_towards(&self, p: [f64; 2]) -> f64 {
+        let (a, b) = self.edge(self.edge_at(p[1].atan2(p[0])));
+        let len = p[0].hypot(p[1]);
+        match intersect_2d([0.0, 0.0], [p[0] / len, p[1] / len], a, b) {
+            Some((t, _)) => t,
+            None => a[0].hypot(a[1]).max(b[0].hypot(b[1])),
+        }
+    }
+
+    /// Finds where a ray in local coordinates strikes the toothed side of the gear, along with the
+    /// index of the profile edge it strikes.
+    fn hit_sides(&self, origin: [f64; 3], dir: [f64; 3]) -> Option<(f64, usize)> {
+        let (o, d) = ([origin[0], origin[1]], [dir[0], dir[1]]);
+        let (t_in, t_out) = intersect_circle_2d(o, d, self.outer_radius)?;
+        if t_out < 0.0 {
+            return None;
+        }
+        let t_in = t_in.max(0.0);
+
+        // As seen from the axis, a line sweeps around in one direction, so only the edges
+        // between the angles at which it enters and leaves the profile's bounding circle can be
+        // struck.
+        let count = self.profile.len();
+        let cross = o[0] * d[1] - o[1] * d[0];
+        let edges: Vec<usize> = if cross.abs() < 1.0e-12 {
+            (0..count).collect()
+        } else {
+            let at = |t: f64| [o[0] + d[0] * t, o[1] + d[1] * t];
+            let (p_in, p_out) = (at(t_in), at(t_out));
+            let first = self.edge_at(p_in[1].atan2(p_in[0]));
+            let last = self.edge_at(p_out[1].atan2(p_out[0]));
+            let (from, steps) = if cross > 0.0 {
+                (first, (last + count - first) % count)
+            } else {
+                (last, (first + count - last) % count)
+            };
+            // Include a neighbor on each side to be safe around shared corners.
+            (0..steps + 3)
+                .map(|i| (from + count - 1 + i) % count)
+                .collect()
+        };
+
+        let half = self.face_width / 2.0;
+        closest_hit(edges.into_iter().filter_map(|ndx| {
+            let (a, b) = self.edge(ndx);
+            let (t, s) = intersect_2d(o, d, a, b)?;
+            let z = origin[2] + dir[2] * t;
+            ((0.0..=1.0).contains(&s) && z.abs() <= half).then_some((t, ndx))
+        }))
+    }
+
+    /// The outward normal of each profile edge in the plane of the profile.
+    fn edge_normal(&self, ndx: usize) -> [f64; 2] {
+        let (a, b) = self.edge(ndx);
+        let (dx, dy) = (b[0] - a[0], b[1] - a[1]);
+        let len = dx.hypot(dy);
+        [dy / len, -dx / len]
+    }
+}
+
+/// Intersects the line `o + t * d` with the segment from `a` to `b`, returning `t` and the
+/// fraction of the way along the segment.
+fn intersect_2d(o: [f64; 2], d: [f64; 2], a: [f64; 2], b: [f64; 2]) -> Option<(f64, f64)> {
+    let e = [b[0] - a[0], b[1] - a[1]];
+    let denom = d[0] * e[1] - d[1] * e[0];
+    if denom == 0.0 {
+        return None;
+    }
+    let w = [a[0] - o[0], a[1] - o[1]];
+    let t = (w[0] * e[1] - w[1] * e[0]) / denom;
+    let s = (w[0] * d[1] - w[1] * d[0]) / denom;
+    Some((t, s))
+}
+
+/// Intersects the line `o + t * d` with a circle around the origin.
+fn intersect_circle_2d(o: [f64; 2], d: [f64; 2], radius: f64) -> Option<(f64, f64)> {
+    let a = d[0] * d[0] + d[1] * d[1];
+    if a == 0.0 {
+        return None;
+    }
+    let b = 2.0 * (o[0] * d[0] + o[1] * d[1]);
+    let c = o[0] * o[0] + o[1] * o[1] - radius * radius;
+    let disc = b * b - 4.0 * a * c;
+    if disc < 0.0 {
+        return None;
+    }
+    let sq = disc.sqrt();
+    Some(((-b - sq) / (2.0 * a), (-b + sq) / (2.0 * a)))
+}
+
+fn polar(radius: f64, angle: f64) -> [f64; 2] {
+    [radius * angle.cos(), radius * angle.sin()]
+}
+
+fn involute(angle: f64) -> f64 {
+    angle.tan() - angle
+}
+
+/// Traces the outline of an involute spur gear.
+fn spur_profile(module: f64, teeth: usize, pressure_angle: f64) -> Vec<[f64; 2]> {
+    let z = teeth as f64;
+    let pitch_radius = module * z / 2.0;
+    let base_radius = pitch_radius * pressure_angle.cos();
+    let tip_radius = pitch_radius + module;
+    let root_radius = pitch_radius - 1.25 * module;
+
+    // The angle from the middle of a tooth to its flank, at a given radius.
+    let half_thickness = |r: f64| {
+        let r = r.max(base_radius);
+        PI / (2.0 * z) + involute(pressure_angle) - involute((base_radius / r).acos())
+    };
+
+    // Small gears come to a point before they reach the addendum circle.
+    let tip_radius = if half_thickness(tip_radius) > 0.0 {
+        tip_radius
+    } else {
+        let (mut lo, mut hi) = (base_radius, tip_radius);
+        for _ in 0..50 {
+            let mid = (lo + hi) / 2.0;
+            if half_thickness(mid) > 0.0 {
+                lo = mid;
+            } else {
+                hi = mid;
+            }
+        }
+        lo
+    };
+    let flank_start = root_radius.max(base_radius);
+
+    let mut profile = Vec::new();
+    for tooth in 0..teeth {
+        let mid = TAU * tooth as f64 / z;
+        let gap = PI / z;
+
+        // root circle, from the middle of the previous gap
+        let root_end = mid - half_thickness(flank_start);
+        for i in 0..ARC_SAMPLES {
+            let f = i as f64 / ARC_SAMPLES as f64;
+            profile.push(polar(root_radius, mid - gap + f * (root_end - (mid - gap))));
+        }
+        if root_radius < base_radius {
+            profile.push(polar(root_radius, root_end));
+        }
+
+        // rising flank
+        for i in 0..=FLANK_SAMPLES {
+            let r = flank_start + (tip_radius - flank_start) * i as f64 / FLANK_SAMPLES as f64;
+            profile.push(polar(r, mid - half_thickness(r)));
+        }
+
+        // tip land
+        let tip = half_thickness(tip_radius);
+        for i in 1..ARC_SAMPLES {
+            let f = i as f64 / ARC_SAMPLES as f64;
+            profile.push(polar(tip_radius, mid - tip + 2.0 * tip * f));
+        }
+
+        // falling flank
+        for i in (0..=FLANK_SAMPLES).rev() {
+            let r = flank_start + (tip_radius - flank_start) * i as f64 / FLANK_SAMPLES as f64;
+            profile.push(polar(r, mid + half_thickness(r)));
+        }
+        if root_radius < base_radius {
+            profile.push(polar(root_radius, mid + half_thickness(flank_start)));
+        }
+
+        // root circle, to the middle of the next gap
+        let root_start = mid + half_thickness(flank_start);
+        for i in 1..ARC_SAMPLES {
+            let f = i as f64 / ARC_SAMPLES as f64;
+            profile.push(polar(
+                root_radius,
+                root_start + f * (mid + gap - root_start),
+            ));
+        }
+    }
+
+    dedup_profile(profile)
+}
+
+/// Traces the outline of a sprocket for a roller chain.
+///
+/// Each tooth gap is a seating arc slightly larger than a roller, centered on the pitch circle,
+/// which continues into straight flanks that run out to the outer circle.
+fn sprocket_profile(pitch: f64, roller_diameter: f64, teeth: usize) -> Vec<[f64; 2]> {
+    // There's no outline to trace around fewer teeth than this, which `validate` reports.
+    if teeth < 3 {
+        return Vec::new();
+    }
+    let n = teeth as f64;
+    let pitch_radius = pitch / (2.0 * (PI / n).sin());
+    let outer_radius = pitch * (0.6 + 1.0 / (PI / n).tan()) / 2.0;
+    let seat_radius = 0.505 * roller_diameter / 2.0;
+    let seat_angle = (70.0 - 45.0 / n).to_radians();
+
+    // Traces the flank on one side of a gap centered on the x axis, from the bottom of the
+    // seating arc out to the tooth's tip.
+    let half_gap = |side: f64| {
+        let mut points = Vec::new();
+        for i in 0..=FLANK_SAMPLES {
+            let beta = seat_angle * i as f64 / FLANK_SAMPLES as f64;
+            points.push([
+                pitch_radius - seat_radius * beta.cos(),
+                side * seat_radius * beta.sin(),
+            ]);
+        }
+
+        // The flank continues along the tangent of the seating arc, until it reaches the outer
+        // circle or the middle of the tooth.
+        let start = *points.last().unwrap();
+        let dir = [seat_angle.sin(), side * seat_angle.cos()];
+        let tooth_mid = PI / n;
+        let past_end = |t: f64| {
+            let p = [start[0] + dir[0] * t, start[1] + dir[1] * t];
+            p[0].hypot(p[1]) >= outer_radius || (side * p[1]).atan2(p[0]) >= tooth_mid
+        };
+        let (mut lo, mut hi) = (0.0, 2.0 * outer_radius);
+        for _ in 0..50 {
+            let mid = (lo + hi) / 2.0;
+            if past_end(mid) {
+                hi = mid;
+            } else {
+                lo = mid;
+            }
+        }
+        for i in 1..=FLANK_SAMPLES {
+            let t = lo * i as f64 / FLANK_SAMPLES as f64;
+            points.push([start[0] + dir[0] * t, start[1] + dir[1] * t]);
+        }
+        points
+    };
+
+    let rising = half_gap(1.0);
+    let mut falling = half_gap(-1.0);
+    falling.reverse();
+    let tip_start = rising.last().unwrap();
+    let tip_angle = tip_start[1].atan2(tip_start[0]);
+
+    let mut profile = Vec::new();
+    for tooth in 0..teeth {
+        let rotation = TAU * tooth as f64 / n;
+        let rotate = |p: &[f64; 2]| {
+            let (s, c) = rotation.sin_cos();
+            [p[0] * c - p[1] * s, p[0] * s + p[1] * c]
+        };
+
+        profile.extend(falling.iter().map(rotate));
+        profile.extend(rising.iter().skip(1).map(rotate));
+
+        // outer circle, up to the flank of the next gap
+        let end_angle = TAU / n - tip_angle;
+        for i in 1..ARC_SAMPLES {
+            let f = i as f64 / ARC_SAMPLES as f64;
+            let angle = tip_angle + f * (end_angle - tip_angle);
+            profile.push(rotate(&polar(outer_radius, angle)));
+        }
+    }
+
+    // Start the outline at the middle of a tooth, so that it never begins mid-gap.
+    let first = profile.len() - ARC_SAMPLES / 2;
+    profile.rotate_left(first);
+    dedup_profile(profile)
+}
+
+fn dedup_profile(mut profile: Vec<[f64; 2]>) -> Vec<[f64; 2]> {
+    profile.dedup_by(|a, b| (a[0] - b[0]).hypot(a[1] - b[1]) < 1.0e-12);
+    profile
+}
+
+impl Shape<WorldSpace> for Gear {
+    fn hit_by(&self, ray: &Ray) -> Option<HitData> {
+        let origin = self.to_local(ray.point);
+        let dir = [
+            ray.dir.dot(self.u),
+            ray.dir.dot(self.v),
+            ray.dir.dot(self.axis),
+        ];
+        let half = self.face_width / 2.0;
+
+        let mut candidates = Vec::new();
+
+        // flat faces
+        if dir[2] != 0.0 {
+            for (side, sign) in [(-half, -1.0), (half, 1.0)] {
+                let t = (side - origin[2]) / dir[2];
+                let p = [origin[0] + dir[0] * t, origin[1] + dir[1] * t];
+                let r = p[0].hypot(p[1]);
+                if r >= self.bore_radius && r <= self.outer_radius && r <= self.radius_towards(p) {
+                    candidates.push((t, self.axis * sign));
+                }
+            }
+        }
+
+        // teeth
+        if let Some((t, ndx)) = self.hit_sides(origin, dir) {
+            let [nx, ny] = self.edge_normal(ndx);
+            candidates.push((t, self.to_world_vec(nx, ny, 0.0)));
+        }
+
+        // bore
+        if self.bore_radius > 0.0 {
+            let local_ray = Ray::new(WPoint3::from(origin), WVec3::from(dir));
+            if let Some((t0, t1)) = ray_cylinder(
+                &local_ray,
+                WPoint3::origin(),
+                WVec3::new(0.0, 0.0, 1.0),
+                self.bore_radius,
+            ) {
+                for t in [t0, t1] {
+                    if (origin[2] + dir[2] * t).abs() <= half {
+                        // the bore faces in towards the axis
+                        let x = -(origin[0] + dir[0] * t) / self.bore_radius;
+                        let y = -(origin[1] + dir[1] * t) / self.bore_radius;
+                        candidates.push((t, self.to_world_vec(x, y, 0.0)));
+                    }
+                }
+            }
+        }
+
+        closest_hit(candidates).map(|(t, normal)| {
+            HitData::new(ray.point + ray.dir * t, t)
+                .with_normal(normal)
+                .with_tag(self.tag)
+        })
+    }
+
+    fn paths(&self) -> Vec<LineSegment<WorldSpace>> {
+        let half = self.face_width / 2.0;
+        let mut paths = Vec::new();
+
+        for z in [-half, half] {
+            for ndx in 0..self.profile.len() {
+                let (a, b) = self.edge(ndx);
+                paths.push(LineSegment::tagged(
+                    self.to_world(a[0], a[1], z),
+                    self.to_world(b[0], b[1], z),
+                    self.tag,
+                ));
+            }
+
+            if self.bore_radius > 0.0 {
+                let segments = super::util::CIRCLE_SEGMENTS;
+                for i in 0..segments {
+                    let a = polar(self.bore_radius, TAU * i as f64 / segments as f64);
+                    let b = polar(self.bore_radius, TAU * (i + 1) as f64 / segments as f64);
+                    paths.push(LineSegment::tagged(
+                        self.to_world(a[0], a[1], z),
+                        self.to_world(b[0], b[1], z),
+                        self.tag,
+                    ));
+                }
+            }
+        }
+
+        paths
+    }
+
+    fn silhouette_paths(&self, eye: WPoint3) -> Vec<LineSegment<WorldSpace>> {
+        let eye_local = self.to_local(eye);
+        let half = self.face_width / 2.0;
+        let count = self.profile.len();
+        let mut paths = Vec::new();
+
+        // The side walls are vertical, so a corner of the profile lies on the silhouette when one
+        // of its walls faces the eye and the other faces away.
+        let faces_eye = |ndx: usize| {
+            let (a, _) = self.edge(ndx);
+            let n = self.edge_normal(ndx);
+            n[0] * (eye_local[0] - a[0]) + n[1] * (eye_local[1] - a[1]) > 0.0
+        };
+        for ndx in 0..count {
+            let prev = (ndx + count - 1) % count;
+            if faces_eye(prev) != faces_eye(ndx) {
+                let [x, y] = self.profile[ndx];
+                paths.push(LineSegment::tagged(
+                    self.to_world(x, y, -half),
+                    self.to_world(x, y, half),
+                    self.tag,
+                ));
+            }
+        }
+
+        if self.bore_radius > 0.0 {
+            let base = self.to_world(0.0, 0.0, -half);
+            for normal in cylinder_silhouette(base, self.axis, self.bore_radius, eye) {
+                let offset = normal * self.bore_radius;
+                paths.push(LineSegment::tagged(
+                    base + offset,
+                    base + offset + self.axis * self.face_width,
+                    self.tag,
+                ));
+            }
+        }
+
+        paths
+    }
+
+    fn bounding_box(&self) -> Option<crate::AABB<crate::WorldSpace>> {
+        let half = self.face_width / 2.0;
+        let r = self.outer_radius;
+        let mut corners = Vec::with_capacity(8);
+        for x in [-r, r] {
+            for y in [-r, r] {
+                for z in [-half, half] {
+                    corners.push(self.to_world(x, y, z));
+                }
+            }
+        }
+        Some(AABB::from_points(corners))
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        let root_radius = self
+            .profile
+            .iter()
+            .map(|p| p[0].hypot(p[1]))
+            .fold(f64::INFINITY, f64::min);
+        if self.teeth < 3 {
+            Err(format!("it has {} teeth, but needs at least 3", self.teeth))
+        } else if !(self.face_width.is_finite() && self.face_width > 0.0) {
+            Err(format!(
+                "its face width of {} isn't positive",
+                self.face_width
+            ))
+        } else if !self.axis.to_array().iter().all(|c| c.is_finite()) {
+            Err("its axis has no length".to_string())
+        } else if !(root_radius > 0.0 && self.profile.iter().flatten().all(|c| c.is_finite())) {
+            Err("its teeth have no size, since its module or pitch isn't positive".to_string())
+        } else if self.bore_radius.is_nan() || self.bore_radius < 0.0 {
+            Err(format!(
+                "its bore of {} isn't zero or positive",
+                2.0 * self.bore_radius
+            ))
+        } else if self.bore_radius >= root_radius {
+            Err(format!(
+                "its bore of {} reaches its root circle, of diameter {}",
+                2.0 * self.bore_radius,
+                2.0 * root_radius
+            ))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn gear() -> Gear {
+        Gear::spur(
+            WPoint3::origin(),
+            WVec3::new(0.0, 0.0, 1.0),
+            1.0,
+            20,
+            20.0,
+            2.0,
+            4.0,
+        )
+    }
+
+    /// A ray heading towards the axis of a gear, at a given angle from the gear's first tooth.
+    fn inward_ray(gear: &Gear, angle: f64, distance: f64) -> Ray {
+        let dir = gear.u * angle.cos() + gear.v * angle.sin();
+        Ray::new(WPoint3::origin() + dir * distance, -dir)
+    }
+
+    #[test]
+    fn test_spur_hit_by() {
+        let gear = gear();
+
+        // the tip of a tooth lies on the addendum circle
+        let hit = gear.hit_by(&inward_ray(&gear, 0.0, 20.0)).unwrap();
+        assert!((hit.dist_to - 9.0).abs() < 1.0e-9);
+
+        // the middle of a gap lies on the dedendum circle
+        let hit = gear.hit_by(&inward_ray(&gear, PI / 20.0, 20.0)).unwrap();
+        assert!((hit.dist_to - 11.25).abs() < 1.0e-9);
+
+        // the face of the gear
+        assert_eq!(
+            gear.hit_by(&Ray::new(
+                WPoint3::new(3.0, 3.0, 5.0),
+                WVec3::new(0.0, 0.0, -1.0)
+            )),
+            Some(HitData::new(WPoint3::new(3.0, 3.0, 1.0), 4.0))
+        );
+
+        // straight through the bore
+        assert_eq!(
+            gear.hit_by(&Ray::new(
+                WPoint3::new(1.0, 1.0, 5.0),
+                WVec3::new(0.0, 0.0, -1.0)
+            )),
+            None
+        );
+
+        // from inside the bore, across to its wall
+        assert_eq!(
+            gear.hit_by(&Ray::new(
+                WPoint3::new(0.0, 0.0, 0.5),
+                WVec3::new(1.0, 0.0, 0.0)
+            )),
+            Some(HitData::new(WPoint3::new(2.0, 0.0, 0.5), 2.0))
+        );
+
+        // over the top of the gear
+        assert_eq!(
+            gear.hit_by(&Ray::new(
+                WPoint3::new(-20.0, 0.0, 1.5),
+                WVec3::new(1.0, 0.0, 0.0)
+            )),
+            None
+        );
+    }
+
+    #[test]
+    fn test_sprocket_hit_by() {
+        let (pitch, roller, teeth) = (12.7, 7.92, 18);
+        let sprocket = Gear::sprocket(
+            WPoint3::origin(),
+            WVec3::new(0.0, 0.0, 1.0),
+            pitch,
+            roller,
+            teeth,
+            3.0,
+            10.0,
+        );
+        let pitch_radius = pitch / (2.0 * (PI / teeth as f64).sin());
+        let outer_radius = pitch * (0.6 + 1.0 / (PI / teeth as f64).tan()) / 2.0;
+
+        // the bottom of a roller seat, centered on the pitch circle
+        let hit = sprocket.hit_by(&inward_ray(&sprocket, 0.0, 100.0)).unwrap();
+        let seat_bottom = pitch_radius - 0.505 * roller / 2.0;
+        assert!((hit.dist_to - (100.0 - seat_bottom)).abs() < 1.0e-6);
+
+        // the tip of a tooth, between two seats
+        let hit = sprocket
+            .hit_by(&inward_ray(&sprocket, PI / teeth as f64, 100.0))
+            .unwrap();
+        assert!(hit.dist_to >= 100.0 - outer_radius - 1.0e-6);
+        assert!(hit.dist_to < 100.0 - pitch_radius);
+    }
+
+    #[test]
+    fn test_validate() {
+        let axis = WVec3::new(0.0, 0.0, 1.0);
+        let spur = |module, teeth, face_width, bore| {
+            Gear::spur(
+                WPoint3::origin(),
+                axis,
+                module,
+                teeth,
+                20.0,
+                face_width,
+                bore,
+            )
+            .validate()
+        };
+        assert!(gear().validate().is_ok());
+        assert!(spur(1.0, 2, 2.0, 1.0).unwrap_err().contains("teeth"));
+        assert!(spur(1.0, 20, 0.0, 4.0).unwrap_err().contains("face width"));
+        assert!(spur(0.0, 20, 2.0, 4.0).unwrap_err().contains("module"));
+        assert!(spur(1.0, 20, 2.0, -1.0).unwrap_err().contains("bore"));
+        // the root circle of this gear has a diameter of 17.5
+        assert!(spur(1.0, 20, 2.0, 17.0).is_ok());
+        assert!(spur(1.0, 20, 2.0, 18.0)
+            .unwrap_err()
+            .contains("root circle"));
+
+        for teeth in [0, 1, 2] {
+            let sprocket = Gear::sprocket(WPoint3::origin(), axis, 12.7, 7.92, teeth, 3.0, 2.0);
+            assert!(sprocket.validate().unwrap_err().contains("teeth"));
+        }
+        let sprocket = Gear::sprocket(WPoint3::origin(), axis, 12.7, 7.92, 3, 3.0, 2.0);
+        assert!(sprocket.validate().is_ok());
+        let zero_axis = Gear::spur(WPoint3::origin(), WVec3::zero(), 1.0, 20, 20.0, 2.0, 4.0);
+        assert!(zero_axis.validate().unwrap_err().contains("axis"));
+    }
+
+    #[test]
+    fn test_tagged() {
+        let axis = WVec3::new(0.0, 0.0, 1.0);
+        let spur = Gear::spur_tagged(WPoint3::origin(), axis, 1.0, 20, 20.0, 2.0, 4.0, 5);
+        let sprocket = Gear::sprocket_tagged(WPoint3::origin(), axis, 12.7, 7.92, 18, 3.0, 10.0, 6);
+        for (gear, tag) in [(spur, 5), (sprocket, 6)] {
+            let hit = gear.hit_by(&inward_ray(&gear, 0.0, 100.0)).unwrap();
+            assert_eq!(hit.tag, tag);
+            assert!(gear.paths().iter().all(|path| path.tag == tag));
+        }
+        assert_eq!(gear().tag, 0);
+    }
+}
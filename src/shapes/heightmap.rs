@@ -0,0 +1,694 @@
+use std::fmt;
+use std::path::Path;
+
+use super::util::closest_hit;
+use crate::path::LineSegment;
+use crate::{HitData, Ray, Shape, WPoint3, WVec3, WorldSpace, AABB};
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+/// The lines which are drawn to describe a `Heightmap`.
+pub enum HeightmapStyle {
+    /// Lines of constant height, spaced `interval` apart vertically.
+    Contours { interval: f64 },
+    /// Profiles of the terrain cut by planes of constant x, spaced `spacing` apart.
+    CrossSectionsX { spacing: f64 },
+    /// Profiles of the terrain cut by planes of constant y, spaced `spacing` apart.
+    CrossSectionsY { spacing: f64 },
+    /// The grid of samples, drawing every `stride`th row and column.
+    Wireframe { stride: usize },
+}
+
+#[derive(Debug)]
+/// An error encountered while loading a heightmap from an image.
+pub enum HeightmapError {
+    Io(std::io::Error),
+    Png(png::DecodingError),
+    /// The image is not in a supported format, or is malformed.
+    Format(String),
+}
+
+impl fmt::Display for HeightmapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "Failed to read heightmap: {}", e),
+            Self::Png(e) => write!(f, "Failed to decode PNG heightmap: {}", e),
+            Self::Format(msg) => write!(f, "Invalid heightmap image: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for HeightmapError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            Self::Png(e) => Some(e),
+            Self::Format(_) => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for HeightmapError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl From<png::DecodingError> for HeightmapError {
+    fn from(e: png::DecodingError) -> Self {
+        Self::Png(e)
+    }
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(test, derive(PartialEq))]
+/// A terrain surface built from a regular grid of height samples.
+///
+/// The grid lies in the xy plane starting at `origin`, with heights rising along the z axis. Each
+/// grid cell is split into two triangles.
+pub struct Heightmap {
+    pub origin: WPoint3,
+    /// The number of samples along the x and y axes.
+    dims: [usize; 2],
+    /// The height of each sample above `origin`, row by row along the x axis.
+    heights: Vec<f64>,
+    pub cell_size: f64,
+    pub styles: Vec<HeightmapStyle>,
+    min_height: f64,
+    max_height: f64,
+    pub tag: usize,
+}
+
+impl Heightmap {
+    pub fn new(
+        origin: WPoint3,
+        dims: [usize; 2],
+        samples: Vec<f64>,
+        cell_size: f64,
+        vertical_scale: f64,
+    ) -> Heightmap {
+        Self::tagged(origin, dims, samples, cell_size, vertical_scale, 0)
+    }
+
+    /// Creates a heightmap from `dims[0] * dims[1]` samples, row by row along the x axis. Each
+    /// sample is multiplied by `vertical_scale` to find its height.
+    pub fn tagged(
+        origin: WPoint3,
+        dims: [usize; 2],
+        samples: Vec<f64>,
+        cell_size: f64,
+        vertical_scale: f64,
+        tag: usize,
+    ) -> Heightmap {
+        assert!(
+            dims[0] >= 2 && dims[1] >= 2,
+            "A heightmap needs at least two samples along each axis"
+        );
+        assert_eq!(samples.len(), dims[0] * dims[1]);
+
+        let heights: Vec<_> = samples.into_iter().map(|s| s * vertical_scale).collect();
+        let min_height = heights.iter().cloned().fold(f64::INFINITY, f64::min);
+        let max_height = heights.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+
+        Heightmap {
+            origin,
+            dims,
+            heights,
+            cell_size,
+            styles: vec![HeightmapStyle::Wireframe { stride: 1 }],
+            min_height,
+            max_height,
+            tag,
+        }
+    }
+
+    /// Loads a heightmap from a grayscale PGM or PNG image, where black is the lowest point and
+    /// white is `vertical_scale` above it. The top row of the image is placed at the far end of
+    /// the y axis.
+    pub fn load<P: AsRef<Path>>(
+        path: P,
+        origin: WPoint3,
+        cell_size: f64,
+        vertical_scale: f64,
+    ) -> Result<Heightmap, HeightmapError> {
+        let bytes = std::fs::read(path)?;
+        let (dims, samples) = if bytes.starts_with(b"\x89PNG") {
+            decode_png(&bytes)?
+        } else if bytes.starts_with(b"P2") || bytes.starts_with(b"P5") {
+            decode_pgm(&bytes)?
+        } else {
+            return Err(HeightmapError::Format(
+                "expected a PGM or PNG image".to_string(),
+            ));
+        };
+
+        if dims[0] < 2 || dims[1] < 2 {
+            return Err(HeightmapError::Format(format!(
+                "image of size {}x{} is too small",
+                dims[0], dims[1]
+            )));
+        }
+
+        // Images are stored top row first.
+        let samples = samples.chunks(dims[0]).rev().flatten().cloned().collect();
+        Ok(Self::new(origin, dims, samples, cell_size, vertical_scale))
+    }
+
+    /// Replaces the lines drawn for this heightmap with a single style.
+    pub fn with_style(mut self, style: HeightmapStyle) -> Self {
+        self.styles = vec![style];
+        self
+    }
+
+    pub fn dims(&self) -> [usize; 2] {
+        self.dims
+    }
+
+    /// The height above `origin` of the sample at column `x` and row `y`.
+    pub fn height(&self, x: usize, y: usize) -> f64 {
+        self.heights[x + y * self.dims[0]]
+    }
+
+    fn point(&self, x: usize, y: usize) -> WPoint3 {
+        self.origin
+            + WVec3::new(
+                x as f64 * self.cell_size,
+                y as f64 * self.cell_size,
+                self.height(x, y),
+            )
+    }
+
+    /// The corners of the two triangles of the cell whose lowest corner is sample `(x, y)`.
+    fn cell_triangles(&self, x: usize, y: usize) -> [[WPoint3; 3]; 2] {
+        let p00 = self.point(x, y);
+        let p10 = self.point(x + 1, y);
+        let p11 = self.point(x + 1, y + 1);
+        let p01 = self.point(x, y + 1);
+        [[p00, p10, p11], [p00, p11, p01]]
+    }
+
+    fn segment(&self, p1: WPoint3, p2: WPoint3) -> LineSegment<WorldSpace> {
+        LineSegment::tagged(p1, p2, self.tag)
+    }
+
+    fn wireframe(&self, stride: usize) -> Vec<LineSegment<WorldSpace>> {
+        let stride = stride.max(1);
+        let [nx, ny] = self.dims;
+        let mut paths = Vec::new();
+        for y in (0..ny).step_by(stride) {
+            for x in 0..(nx - 1) {
+                paths.push(self.segment(self.point(x, y), self.point(x + 1, y)));
+            }
+        }
+        for x in (0..nx).step_by(stride) {
+            for y in 0..(ny - 1) {
+                paths.push(self.segment(self.point(x, y), self.point(x, y + 1)));
+            }
+        }
+        paths
+    }
+
+    /// Cuts the terrain with planes perpendicular to `axis` (0 for x, 1 for y).
+    fn cross_sections(&self, axis: usize, spacing: f64) -> Vec<LineSegment<WorldSpace>> {
+        let extent = (self.dims[axis] - 1) as f64 * self.cell_size;
+        if spacing <= 0.0 {
+            return Vec::new();
+        }
+
+        let mut paths = Vec::new();
+        let mut offset = 0.0;
+        while offset <= extent {
+            let cell = ((offset / self.cell_size).floor() as usize).min(self.dims[axis] - 2);
+            let frac = offset / self.cell_size - cell as f64;
+
+            for other in 0..(self.dims[1 - axis] - 1) {
+                let (x, y) = if axis == 0 {
+                    (cell, other)
+                } else {
+                    (other, cell)
+                };
+                let h00 = self.height(x, y);
+                let h10 = self.height(x + 1, y);
+                let h11 = self.height(x + 1, y + 1);
+                let h01 = self.height(x, y + 1);
+
+                // Points where the cut enters the cell, crosses its diagonal, and leaves it, as
+                // (u, v, height) in cell-local coordinates.
+                let profile = if axis == 0 {
+                    let u = frac;
+                    [
+                        (u, 0.0, h00 + u * (h10 - h00)),
+                        (u, u, h00 + u * (h11 - h00)),
+                        (u, 1.0, h01 + u * (h11 - h01)),
+                    ]
+                } else {
+                    let v = frac;
+                    [
+                        (0.0, v, h00 + v * (h01 - h00)),
+                        (v, v, h00 + v * (h11 - h00)),
+                        (1.0, v, h10 + v * (h11 - h10)),
+                    ]
+                };
+
+                let to_world = |(u, v, h): (f64, f64, f64)| {
+                    self.origin
+                        + WVec3::new(
+                            (x as f64 + u) * self.cell_size,
+                            (y as f64 + v) * self.cell_size,
+                            h,
+                        )
+                };
+                paths.push(self.segment(to_world(profile[0]), to_world(profile[1])));
+                paths.push(self.segment(to_world(profile[1]), to_world(profile[2])));
+            }
+
+            offset += spacing;
+        }
+
+        paths.retain(|path| path.p1 != path.p2);
+        paths
+    }
+
+    fn contours(&self, interval: f64) -> Vec<LineSegment<WorldSpace>> {
+        if interval <= 0.0 {
+            return Vec::new();
+        }
+
+        let base = self.origin.z;
+        let first = ((base + self.min_height) / interval).ceil() as i64;
+        let last = ((base + self.max_height) / interval).floor() as i64;
+
+        let mut paths = Vec::new();
+        for y in 0..(self.dims[1] - 1) {
+            for x in 0..(self.dims[0] - 1) {
+                for tri in &self.cell_triangles(x, y) {
+                    let lo = tri.iter().map(|p| p.z).fold(f64::INFINITY, f64::min);
+                    let hi = tri.iter().map(|p| p.z).fold(f64::NEG_INFINITY, f64::max);
+                    let from = first.max((lo / interval).ceil() as i64);
+                    let to = last.min((hi / interval).floor() as i64);
+
+                    for level in from..=to {
+                        let level = level as f64 * interval;
+                        let crossings: Vec<_> = (0..3)
+                            .filter_map(|i| {
+                                let (a, b) = (tri[i], tri[(i + 1) % 3]);
+                                if (a.z >= level) == (b.z >= level) {
+                                    return None;
+                                }
+                                let t = (level - a.z) / (b.z - a.z);
+                                Some(a.lerp(b, t))
+                            })
+                            .collect();
+                        if let [p1, p2] = crossings[..] {
+                            if p1 != p2 {
+                                paths.push(self.segment(p1, p2));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        paths
+    }
+}
+
+/// Intersects a ray with both sides of a triangle, returning the distance to the hit.
+fn ray_triangle(ray: &Ray, tri: &[WPoint3; 3]) -> Option<f64> {
+    let e1 = tri[1] - tri[0];
+    let e2 = tri[2] - tri[0];
+    let p = ray.dir.cross(e2);
+    let det = e1.dot(p);
+    if det == 0.0 {
+        return None;
+    }
+
+    let inv_det = 1.0 / det;
+    let s = ray.point - tri[0];
+    let u = s.dot(p) * inv_det;
+    if !(0.0..=1.0).contains(&u) {
+        return None;
+    }
+
+    let q = s.cross(e1);
+    let v = ray.dir.dot(q) * inv_det;
+    if v < 0.0 || u + v > 1.0 {
+        return None;
+    }
+
+    let t = e2.dot(q) * inv_det;
+    (t >= 0.0).then_some(t)
+}
+
+impl Shape<WorldSpace> for Heightmap {
+    fn hit_by(&self, ray: &Ray) -> Option<HitData> {
+        let aabb = self.bounding_box().unwrap();
+        let origin = ray.point.to_array();
+        let dir = ray.dir.to_array();
+        let (lo, hi) = (aabb.min.to_array(), aabb.max.to_array());
+
+        let mut t_enter = 0.0f64;
+        let mut t_exit = f64::INFINITY;
+        for axis in 0..3 {
+            if dir[axis] == 0.0 {
+                if origin[axis] < lo[axis] || origin[axis] > hi[axis] {
+                    return None;
+                }
+                continue;
+            }
+            let t1 = (lo[axis] - origin[axis]) / dir[axis];
+            let t2 = (hi[axis] - origin[axis]) / dir[axis];
+            t_enter = t_enter.max(t1.min(t2));
+            t_exit = t_exit.min(t1.max(t2));
+        }
+        if t_exit < t_enter {
+            return None;
+        }
+
+        // Walk the grid cells underneath the ray in order.
+        let start = ray.point + ray.dir * t_enter;
+        let start = [start.x, start.y];
+        let mut cell = [0isize; 2];
+        let mut step = [0isize; 2];
+        let mut t_max = [f64::INFINITY; 2];
+        let mut t_delta = [f64::INFINITY; 2];
+        for axis in 0..2 {
+            let last = self.dims[axis] as isize - 2;
+            let c = ((start[axis] - lo[axis]) / self.cell_size).floor() as isize;
+            cell[axis] = c.max(0).min(last);
+            if dir[axis] > 0.0 {
+                step[axis] = 1;
+            } else if dir[axis] < 0.0 {
+                step[axis] = -1;
+            } else {
+                continue;
+            }
+            let next = cell[axis] + (step[axis] > 0) as isize;
+            let boundary = lo[axis] + next as f64 * self.cell_size;
+            t_max[axis] = (boundary - origin[axis]) / dir[axis];
+            t_delta[axis] = self.cell_size / dir[axis].abs();
+        }
+
+        let mut t_cell_start = t_enter;
+        loop {
+            let t_cell_end = t_max[0].min(t_max[1]).min(t_exit);
+
+            // Skip cells which the ray passes entirely above or below.
+            let (x, y) = (cell[0] as usize, cell[1] as usize);
+            let z1 = origin[2] + dir[2] * t_cell_start;
+            let z2 = origin[2] + dir[2] * t_cell_end;
+            let heights = [
+                self.height(x, y),
+                self.height(x + 1, y),
+                self.height(x + 1, y + 1),
+                self.height(x, y + 1),
+            ];
+            let cell_lo =
+                heights.iter().cloned().fold(f64::INFINITY, f64::min) + lo[2] - self.min_height;
+            let cell_hi =
+                heights.iter().cloned().fold(f64::NEG_INFINITY, f64::max) + lo[2] - self.min_height;
+            if z1.min(z2) <= cell_hi && z1.max(z2) >= cell_lo {
+                let hit = closest_hit(
+                    self.cell_triangles(x, y)
+                        .iter()
+                        .filter_map(|tri| Some((ray_triangle(ray, tri)?, *tri))),
+                );
+                if let Some((t, [a, b, c])) = hit {
+                    let hit_point = ray.point + ray.dir * t;
+                    let normal = (b - a).cross(c - a).normalize();
+                    let normal = if normal.z < 0.0 { -normal } else { normal };
+                    let extent = (aabb.max - aabb.min).to_array();
+                    return Some(
+                        HitData::new(hit_point, t)
+                            .with_normal(normal)
+                            .with_tag(self.tag)
+                            .with_uv(
+                                (hit_point.x - lo[0]) / extent[0],
+                                (hit_point.y - lo[1]) / extent[1],
+                            ),
+                    );
+                }
+            }
+
+            if t_cell_end >= t_exit {
+                return None;
+            }
+            let axis = if t_max[0] < t_max[1] { 0 } else { 1 };
+            cell[axis] += step[axis];
+            if cell[axis] < 0 || cell[axis] > self.dims[axis] as isize - 2 {
+                return None;
+            }
+            t_cell_start = t_max[axis];
+            t_max[axis] += t_delta[axis];
+        }
+    }
+
+    fn paths(&self) -> Vec<LineSegment<WorldSpace>> {
+        self.styles
+            .iter()
+            .flat_map(|style| match *style {
+                HeightmapStyle::Contours { interval } => self.contours(interval),
+                HeightmapStyle::CrossSectionsX { spacing } => self.cross_sections(0, spacing),
+                HeightmapStyle::CrossSectionsY { spacing } => self.cross_sections(1, spacing),
+                HeightmapStyle::Wireframe { stride } => self.wireframe(stride),
+            })
+            .collect()
+    }
+
+    fn bounding_box(&self) -> Option<crate::AABB<crate::WorldSpace>> {
+        let extent = WVec3::new(
+            (self.dims[0] - 1) as f64 * self.cell_size,
+            (self.dims[1] - 1) as f64 * self.cell_size,
+            0.0,
+        );
+        let min = self.origin + WVec3::new(0.0, 0.0, self.min_height);
+        let max = self.origin + extent + WVec3::new(0.0, 0.0, self.max_height);
+        Some(AABB::new(min, max))
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if !(self.cell_size > 0.0 && self.cell_size.is_finite()) {
+            Err(format!(
+                "its cell size of {} isn't positive",
+                self.cell_size
+            ))
+        } else if !self.heights.iter().all(|h| h.is_finite()) {
+            Err("its heights aren't all finite".to_string())
+        } else {
+            Ok(())
+        }
+    }
+}
+
+type DecodedImage = ([usize; 2], Vec<f64>);
+
+/// Decodes a binary (P5) or plain (P2) PGM image into samples between 0 and 1.
+fn decode_pgm(bytes: &[u8]) -> Result<DecodedImage, HeightmapError> {
+    let malformed = || HeightmapError::Format("malformed PGM header".to_string());
+
+    // Read the magic number and the three header fields, skipping comments.
+    let mut fields = Vec::with_capacity(4);
+    let mut pos = 0;
+    while fields.len() < 4 {
+        while pos < bytes.len() && (bytes[pos].is_ascii_whitespace() || bytes[pos] == b'#') {
+            if bytes[pos] == b'#' {
+                while pos < bytes.len() && bytes[pos] != b'\n' {
+                    pos += 1;
+                }
+            } else {
+                pos += 1;
+            }
+        }
+        let start = pos;
+        while pos < bytes.len() && !bytes[pos].is_ascii_whitespace() {
+            pos += 1;
+        }
+        if start == pos {
+            return Err(malformed());
+        }
+        fields.push(std::str::from_utf8(&bytes[start..pos]).map_err(|_| malformed())?);
+    }
+
+    let magic = fields[0];
+    let parse = |s: &str| s.parse::<usize>().map_err(|_| malformed());
+    let (width, height, maxval) = (parse(fields[1])?, parse(fields[2])?, parse(fields[3])?);
+    if maxval == 0 || maxval > 65535 {
+        return Err(malformed());
+    }
+    let count = width * height;
+
+    let values: Vec<usize> = match magic {
+        "P5" => {
+            // A single whitespace byte separates the header from the raster.
+            let raster = bytes.get(pos + 1..).unwrap_or(&[]);
+            if maxval < 256 {
+                raster.iter().take(count).map(|b| *b as usize).collect()
+            } else {
+                raster
+                    .chunks_exact(2)
+                    .take(count)
+                    .map(|b| u16::from_be_bytes([b[0], b[1]]) as usize)
+                    .collect()
+            }
+        }
+        "P2" => std::str::from_utf8(&bytes[pos..])
+            .map_err(|_| malformed())?
+            .split_ascii_whitespace()
+            .take(count)
+            .map(parse)
+            .collect::<Result<_, _>>()?,
+        _ => return Err(malformed()),
+    };
+
+    if values.len() != count {
+        return Err(HeightmapError::Format(format!(
+            "expected {} samples but found {}",
+            count,
+            values.len()
+        )));
+    }
+
+    let samples = values
+        .into_iter()
+        .map(|v| v as f64 / maxval as f64)
+        .collect();
+    Ok(([width, height], samples))
+}
+
+/// Decodes a PNG image into samples between 0 and 1, using the luminance of color images.
+fn decode_png(bytes: &[u8]) -> Result<DecodedImage, HeightmapError> {
+    let mut decoder = png::Decoder::new(bytes);
+    decoder.set_transformations(png::Transformations::EXPAND);
+    let mut reader = decoder.read_info()?;
+    let mut buf = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buf)?;
+    let buf = &buf[..info.buffer_size()];
+
+    let values: Vec<f64> = match info.bit_depth {
+        png::BitDepth::Sixteen => buf
+            .chunks_exact(2)
+            .map(|b| u16::from_be_bytes([b[0], b[1]]) as f64 / 65535.0)
+            .collect(),
+        _ => buf.iter().map(|b| *b as f64 / 255.0).collect(),
+    };
+
+    let samples = match info.color_type {
+        png::ColorType::Grayscale => values,
+        png::ColorType::GrayscaleAlpha => values.chunks_exact(2).map(|px| px[0]).collect(),
+        png::ColorType::Rgb => values.chunks_exact(3).map(luminance).collect(),
+        png::ColorType::Rgba => values.chunks_exact(4).map(luminance).collect(),
+        png::ColorType::Indexed => {
+            return Err(HeightmapError::Format(
+                "indexed PNG images are not supported".to_string(),
+            ))
+        }
+    };
+
+    Ok(([info.width as usize, info.height as usize], samples))
+}
+
+fn luminance(px: &[f64]) -> f64 {
+    0.2126 * px[0] + 0.7152 * px[1] + 0.0722 * px[2]
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn pyramid() -> Heightmap {
+        #[rustfmt::skip]
+        let samples = vec![
+            0.0, 0.0, 0.0,
+            0.0, 1.0, 0.0,
+            0.0, 0.0, 0.0,
+        ];
+        Heightmap::new(WPoint3::origin(), [3, 3], samples, 1.0, 2.0)
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(pyramid().validate().is_ok());
+        let flat = Heightmap::new(WPoint3::origin(), [2, 2], vec![0.0; 4], 0.0, 1.0);
+        assert!(flat.validate().unwrap_err().contains("cell size"));
+        let samples = vec![0.0, 1.0, f64::NAN, 0.0];
+        let broken = Heightmap::new(WPoint3::origin(), [2, 2], samples, 1.0, 1.0);
+        assert!(broken.validate().unwrap_err().contains("heights"));
+    }
+
+    #[test]
+    fn test_heightmap_hit_by() {
+        let terrain = pyramid();
+
+        // straight down onto the peak's slope
+        assert_eq!(
+            terrain.hit_by(&Ray::new(
+                WPoint3::new(0.5, 0.75, 5.0),
+                WVec3::new(0.0, 0.0, -1.0)
+            )),
+            Some(HitData::new(WPoint3::new(0.5, 0.75, 1.0), 4.0))
+        );
+
+        // from the side, onto the rising slope
+        assert_eq!(
+            terrain.hit_by(&Ray::normalize_new(
+                WPoint3::new(-1.0, 0.75, 2.0),
+                WVec3::new(1.0, 0.0, -1.0)
+            )),
+            Some(HitData::new(
+                WPoint3::new(1.0 / 3.0, 0.75, 2.0 / 3.0),
+                4.0 / 3.0 * 2.0f64.sqrt()
+            ))
+        );
+
+        // passes over the top
+        assert_eq!(
+            terrain.hit_by(&Ray::new(
+                WPoint3::new(-1.0, 1.0, 2.1),
+                WVec3::new(1.0, 0.0, 0.0)
+            )),
+            None
+        );
+
+        // misses the grid entirely
+        assert_eq!(
+            terrain.hit_by(&Ray::new(
+                WPoint3::new(3.5, 1.0, 5.0),
+                WVec3::new(0.0, 0.0, -1.0)
+            )),
+            None
+        );
+    }
+
+    #[test]
+    fn test_heightmap_paths() {
+        let terrain = pyramid();
+        assert_eq!(terrain.paths().len(), 12);
+
+        // the six triangles which touch the peak each cross the halfway contour once
+        let contours = terrain
+            .clone()
+            .with_style(HeightmapStyle::Contours { interval: 1.0 })
+            .paths();
+        assert_eq!(contours.len(), 6);
+        assert!(contours.iter().all(|path| (path.p1.z - 1.0).abs() < 1.0e-9));
+
+        let sections = terrain
+            .with_style(HeightmapStyle::CrossSectionsX { spacing: 1.0 })
+            .paths();
+        assert_eq!(sections.len(), 6);
+    }
+
+    #[test]
+    fn test_decode_pgm() {
+        let plain = b"P2\n# a comment\n3 2\n10\n0 5 10\n10 5 0\n";
+        let (dims, samples) = decode_pgm(plain).unwrap();
+        assert_eq!(dims, [3, 2]);
+        assert_eq!(samples, vec![0.0, 0.5, 1.0, 1.0, 0.5, 0.0]);
+
+        let mut binary = b"P5 2 2 255\n".to_vec();
+        binary.extend_from_slice(&[0, 51, 102, 255]);
+        let (dims, samples) = decode_pgm(&binary).unwrap();
+        assert_eq!(dims, [2, 2]);
+        assert_eq!(samples, vec![0.0, 0.2, 0.4, 1.0]);
+
+        assert!(decode_pgm(b"P5 2 2 255\n\x00").is_err());
+    }
+}
@@ -0,0 +1,301 @@
+use std::sync::Arc;
+
+use crate::bvh::BVHTree;
+use crate::path::LineSegment;
+use crate::{HitData, Ray, Shape, WPoint3, WVec3, WWTransform, WorldSpace, AABB};
+
+#[derive(Debug)]
+/// A group of shapes which is defined once and placed into a scene any number of times by
+/// `Instance`s.
+///
+/// The prototype builds its own bounding volume hierarchy over its shapes, which every instance
+/// of it shares. A scene made of instances then only has to build its hierarchy over the
+/// instances themselves, rather than over every copy of every shape.
+pub struct Prototype {
+    bvh: BVHTree<WorldSpace>,
+    shapes: Vec<Arc<dyn Shape<WorldSpace>>>,
+    paths: Vec<LineSegment<WorldSpace>>,
+}
+
+impl Prototype {
+    pub fn new(shapes: Vec<Box<dyn Shape<WorldSpace>>>) -> Prototype {
+        let shapes: Vec<Arc<dyn Shape<WorldSpace>>> = shapes.into_iter().map(Arc::from).collect();
+        let bvh = BVHTree::new(&shapes);
+        let paths = shapes.iter().flat_map(|shape| shape.paths()).collect();
+        Prototype { bvh, shapes, paths }
+    }
+}
+
+#[derive(Debug, Clone)]
+/// A prototype placed into the world by an affine transformation, which may translate, rotate,
+/// scale and shear it.
+///
+/// Hits and paths keep the tags of the prototype's shapes, so that instances of a prototype can
+/// be styled just like the shapes it was made from.
+pub struct Instance {
+    prototype: Arc<Prototype>,
+    transform: WWTransform,
+    inverse: WWTransform,
+}
+
+impl Instance {
+    /// Places `prototype` into the world, with its shapes' coordinates transformed by
+    /// `transform`. Returns `None` if the transformation can't be undone, e.g. if it flattens the
+    /// prototype.
+    pub fn new(prototype: Arc<Prototype>, transform: WWTransform) -> Option<Instance> {
+        let inverse = transform.inverse()?;
+        Some(Instance {
+            prototype,
+            transform,
+            inverse,
+        })
+    }
+
+    pub fn prototype(&self) -> &Arc<Prototype> {
+        &self.prototype
+    }
+
+    pub fn transform(&self) -> WWTransform {
+        self.transform
+    }
+
+    fn to_world(&self, point: WPoint3) -> WPoint3 {
+        // The transformation is affine, so this can't fail.
+        self.transform.transform_point3d(point).unwrap_or(point)
+    }
+
+    /// Transforms a surface normal of the prototype into the world. Normals have to be
+    /// transformed by the inverse transpose of the transformation, which keeps them perpendicular
+    /// to the surface under non-uniform scaling and shearing.
+    fn normal_to_world(&self, normal: WVec3) -> WVec3 {
+        let m = &self.inverse;
+        let n = WVec3::new(
+            normal.x * m.m11 + normal.y * m.m12 + normal.z * m.m13,
+            normal.x * m.m21 + normal.y * m.m22 + normal.z * m.m23,
+            normal.x * m.m31 + normal.y * m.m32 + normal.z * m.m33,
+        );
+        let length = n.length();
+        if length > 0.0 {
+            n / length
+        } else {
+            n
+        }
+    }
+}
+
+impl Shape<WorldSpace> for Instance {
+    fn hit_by(&self, ray: &Ray) -> Option<HitData> {
+        // Distances along the transformed ray are scaled by the length of its direction, which
+        // has to be normalized again.
+        if !ray.has_direction() {
+            return None;
+        }
+        let point = self.inverse.transform_point3d(ray.point)?;
+        let dir = self.inverse.transform_vector3d(ray.dir);
+        let scale = dir.length();
+        let local = Ray::bounded(point, dir / scale, ray.tmin * scale, ray.tmax * scale);
+
+        // The shapes of the prototype are an implementation detail, so the hit is reported as
+        // being on the instance as a whole.
+        self.prototype.bvh.closest_hit(local).map(|hit| HitData {
+            hit_point: self.to_world(hit.hit_point),
+            dist_to: hit.dist_to / scale,
+            normal: self.normal_to_world(hit.normal),
+            shape_id: None,
+            ..hit
+        })
+    }
+
+    fn paths(&self) -> Vec<LineSegment<WorldSpace>> {
+        self.prototype
+            .paths
+            .iter()
+            .filter_map(|path| path.transform(&self.transform))
+            .collect()
+    }
+
+    fn silhouette_paths(&self, eye: WPoint3) -> Vec<LineSegment<WorldSpace>> {
+        // Affine transformations preserve tangency, so the silhouettes seen from the eye's
+        // position relative to the prototype are the silhouettes of the instance.
+        let eye = match self.inverse.transform_point3d(eye) {
+            Some(eye) => eye,
+            None => return Vec::new(),
+        };
+        self.prototype
+            .shapes
+            .iter()
+            .flat_map(|shape| shape.silhouette_paths(eye))
+            .filter_map(|path| path.transform(&self.transform))
+            .collect()
+    }
+
+    fn bounding_box(&self) -> Option<AABB<WorldSpace>> {
+        let aabb = self.prototype.bvh.bounding_box()?;
+        self.transform.outer_transformed_box3d(&aabb)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.prototype.shapes.is_empty() {
+            Err("its prototype has no shapes".to_string())
+        } else if !self.transform.to_array().iter().all(|c| c.is_finite()) {
+            Err("its transform isn't finite".to_string())
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::shapes::RectPrism;
+    use crate::{Scene, ShapeId};
+    use euclid::approxeq::ApproxEq;
+    use euclid::Angle;
+
+    fn unit_cube() -> Arc<Prototype> {
+        Arc::new(Prototype::new(vec![Box::new(RectPrism::tagged(
+            WVec3::splat(0.0),
+            WVec3::splat(1.0),
+            7,
+        ))]))
+    }
+
+    #[test]
+    fn test_instance_hit_by() {
+        // stretched along x, and moved
+        let transform =
+            WWTransform::scale(4.0, 1.0, 1.0).then_translate(WVec3::new(10.0, 0.0, 0.0));
+        let instance = Instance::new(unit_cube(), transform).unwrap();
+
+        let hit = instance
+            .hit_by(&Ray::new(
+                WPoint3::new(0.0, 0.5, 0.5),
+                WVec3::new(1.0, 0.0, 0.0),
+            ))
+            .unwrap();
+        assert_eq!(hit, HitData::new(WPoint3::new(10.0, 0.5, 0.5), 10.0));
+        assert!(hit.normal.approx_eq(&WVec3::new(-1.0, 0.0, 0.0)));
+        assert_eq!(hit.tag, 7);
+        assert_eq!(hit.shape_id, None);
+
+        let hit = instance
+            .hit_by(&Ray::new(
+                WPoint3::new(12.0, 0.5, 3.0),
+                WVec3::new(0.0, 0.0, -1.0),
+            ))
+            .unwrap();
+        assert_eq!(hit, HitData::new(WPoint3::new(12.0, 0.5, 1.0), 2.0));
+        assert!(hit.normal.approx_eq(&WVec3::new(0.0, 0.0, 1.0)));
+
+        // passes where the cube would have been, had it not been moved
+        assert_eq!(
+            instance.hit_by(&Ray::new(
+                WPoint3::new(0.5, 0.5, 3.0),
+                WVec3::new(0.0, 0.0, -1.0),
+            )),
+            None
+        );
+
+        // rays without a direction miss
+        for dir in [WVec3::zero(), WVec3::new(f64::NAN, 0.0, 0.0)] {
+            let ray = Ray {
+                point: WPoint3::new(0.0, 0.5, 0.5),
+                dir,
+                tmin: 0.0,
+                tmax: f64::INFINITY,
+            };
+            assert_eq!(instance.hit_by(&ray), None);
+        }
+    }
+
+    #[test]
+    fn test_validate() {
+        let instance = Instance::new(unit_cube(), WWTransform::identity()).unwrap();
+        assert!(instance.validate().is_ok());
+        let empty = Arc::new(Prototype::new(Vec::new()));
+        let instance = Instance::new(empty, WWTransform::identity()).unwrap();
+        assert!(instance.validate().unwrap_err().contains("prototype"));
+    }
+
+    #[test]
+    fn test_instance_sheared_normal() {
+        // shearing x by z makes the sides of the slab lean over
+        let mut transform = WWTransform::identity();
+        transform.m31 = 1.0;
+        let slab = Arc::new(Prototype::new(vec![Box::new(RectPrism::new(
+            WVec3::new(-10.0, -10.0, -1.0),
+            WVec3::new(10.0, 10.0, 0.0),
+        ))]));
+        let instance = Instance::new(slab, transform).unwrap();
+
+        let hit = instance
+            .hit_by(&Ray::new(
+                WPoint3::new(0.0, 0.0, -5.0),
+                WVec3::new(0.0, 0.0, 1.0),
+            ))
+            .unwrap();
+        // the bottom face at z = -1 is shifted by -1 along x, and remains flat
+        assert_eq!(hit, HitData::new(WPoint3::new(0.0, 0.0, -1.0), 4.0));
+        assert!(hit.normal.approx_eq(&WVec3::new(0.0, 0.0, -1.0)));
+
+        let hit = instance
+            .hit_by(&Ray::new(
+                WPoint3::new(20.0, 0.0, -0.5),
+                WVec3::new(-1.0, 0.0, 0.0),
+            ))
+            .unwrap();
+        // the side face at x = 10 leans over, so its normal tilts towards -z
+        assert_eq!(hit, HitData::new(WPoint3::new(9.5, 0.0, -0.5), 10.5));
+        assert!(hit
+            .normal
+            .approx_eq(&WVec3::new(1.0, 0.0, -1.0).normalize()));
+    }
+
+    #[test]
+    fn test_instance_bounding_box() {
+        let transform = WWTransform::rotation(0.0, 0.0, 1.0, Angle::degrees(45.0))
+            .then_translate(WVec3::new(0.0, 0.0, 2.0));
+        let instance = Instance::new(unit_cube(), transform).unwrap();
+        let aabb = instance.bounding_box().unwrap();
+        let half_diagonal = std::f64::consts::SQRT_2 / 2.0;
+
+        assert!(aabb.min.approx_eq(&WPoint3::new(-half_diagonal, 0.0, 2.0)));
+        assert!(aabb
+            .max
+            .approx_eq(&WPoint3::new(half_diagonal, 2.0 * half_diagonal, 3.0)));
+        assert_eq!(instance.paths().len(), 12);
+
+        // flattened, so it can't be undone
+        assert!(Instance::new(unit_cube(), WWTransform::scale(0.0, 1.0, 1.0)).is_none());
+    }
+
+    #[test]
+    fn test_instanced_scene() {
+        let cube = unit_cube();
+        let geometry: Vec<Box<dyn Shape<WorldSpace>>> = (0..10)
+            .map(|i| {
+                let transform = WWTransform::translation(2.0 * i as f64, 0.0, 0.0);
+                Box::new(Instance::new(Arc::clone(&cube), transform).unwrap()) as _
+            })
+            .collect();
+        let scene = Scene::new(geometry);
+        assert_eq!(Arc::strong_count(&cube), 11);
+
+        let hit = scene
+            .intersects(Ray::new(
+                WPoint3::new(6.5, 0.5, 5.0),
+                WVec3::new(0.0, 0.0, -1.0),
+            ))
+            .unwrap();
+        assert_eq!(hit, HitData::new(WPoint3::new(6.5, 0.5, 1.0), 4.0));
+        assert_eq!(hit.shape_id, Some(ShapeId(3)));
+        assert_eq!(hit.tag, 7);
+
+        // between two instances
+        assert!(!scene.occluded(Ray::new(
+            WPoint3::new(7.5, 0.5, 5.0),
+            WVec3::new(0.0, 0.0, -1.0),
+        )));
+    }
+}
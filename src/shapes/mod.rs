@@ -1,9 +1,26 @@
+pub use self::capsule::Capsule;
+pub use self::gear::Gear;
+pub use self::heightmap::{Heightmap, HeightmapError, HeightmapStyle};
+pub use self::instance::{Instance, Prototype};
 pub use self::plane::Plane;
+pub use self::polygon::Polygon;
+pub use self::polyhedron::Polyhedron;
 pub use self::rectprism::RectPrism;
+pub use self::roundedbox::RoundedBox;
 pub use self::sphere::Sphere;
 pub use self::triangle::Triangle;
+pub use self::voxelgrid::VoxelGrid;
 
+pub mod capsule;
+pub mod gear;
+pub mod heightmap;
+pub mod instance;
 pub mod plane;
+pub mod polygon;
+pub mod polyhedron;
 pub mod rectprism;
+pub mod roundedbox;
 pub mod sphere;
 pub mod triangle;
+pub(crate) mod util;
+pub mod voxelgrid;
@@ -7,13 +7,23 @@ use crate::{HitData, Ray, Shape, WPoint3, WVec3, WorldSpace};
 pub struct Plane {
     /// An arbitrary point in space which exists on the plane.
     pub point: WPoint3,
-    /// A normal vector to the plane.
+    /// A normal vector to the plane. It doesn't need to be normalized.
     pub normal: WVec3,
+    tag: usize,
 }
 
 impl Plane {
     pub fn new(point: WPoint3, normal: WVec3) -> Plane {
-        Plane { point, normal }
+        Self::tagged(point, normal, 0)
+    }
+
+    pub fn tagged(point: WPoint3, normal: WVec3, tag: usize) -> Plane {
+        Plane { point, normal, tag }
+    }
+
+    /// The tag given to hits on the plane.
+    pub fn tag(&self) -> usize {
+        self.tag
     }
 }
 
@@ -31,7 +41,11 @@ impl Shape<WorldSpace> for Plane {
         }
 
         let hit_point = ray.point + (ray.dir.normalize() * t);
-        Some(HitData::new(hit_point, t))
+        Some(
+            HitData::new(hit_point, t)
+                .with_normal(self.normal.normalize())
+                .with_tag(self.tag),
+        )
     }
 
     fn paths(&self) -> Vec<LineSegment<WorldSpace>> {
@@ -41,6 +55,17 @@ impl Shape<WorldSpace> for Plane {
     fn bounding_box(&self) -> Option<crate::AABB<crate::WorldSpace>> {
         None
     }
+
+    fn validate(&self) -> Result<(), String> {
+        let (p, n) = (self.point, self.normal);
+        if ![p.x, p.y, p.z, n.x, n.y, n.z].iter().all(|v| v.is_finite()) {
+            Err("its point or normal isn't finite".to_string())
+        } else if self.normal.square_length() == 0.0 {
+            Err("its normal has no length".to_string())
+        } else {
+            Ok(())
+        }
+    }
 }
 
 #[cfg(test)]
@@ -91,4 +116,22 @@ mod test {
             None
         );
     }
+
+    #[test]
+    fn test_hit_normal_and_tag() {
+        let plane = Plane::tagged(WPoint3::new(1.0, 0.0, 0.0), WVec3::new(-3.0, 0.0, 0.0), 7);
+        let hit = plane
+            .hit_by(&Ray::new(
+                WPoint3::new(0.0, 0.0, 0.0),
+                WVec3::new(1.0, 0.0, 0.0),
+            ))
+            .unwrap();
+        assert_eq!(hit.normal, WVec3::new(-1.0, 0.0, 0.0));
+        assert_eq!(hit.tag, 7);
+        assert_eq!(plane.tag(), 7);
+        assert_eq!(
+            Plane::new(WPoint3::origin(), WVec3::new(0.0, 1.0, 0.0)).tag(),
+            0
+        );
+    }
 }
@@ -0,0 +1,123 @@
+use super::plane::Plane;
+use crate::path::LineSegment;
+use crate::{HitData, Ray, Shape, WPoint3, WorldSpace};
+
+#[derive(Debug, Clone)]
+#[cfg_attr(test, derive(PartialEq))]
+/// A flat, convex polygon. Unlike a fan of `Triangle`s, only its outer edges are drawn.
+pub struct Polygon {
+    /// The corners of the polygon, in winding order.
+    pub verts: Vec<WPoint3>,
+    pub plane: Plane,
+    pub tag: usize,
+}
+
+impl Polygon {
+    pub fn new(verts: Vec<WPoint3>) -> Polygon {
+        Self::tagged(verts, 0)
+    }
+
+    /// Creates a polygon from at least three coplanar points which wind around a convex shape.
+    pub fn tagged(verts: Vec<WPoint3>, tag: usize) -> Polygon {
+        assert!(verts.len() >= 3, "A polygon needs at least three corners");
+        let normal = (verts[1] - verts[0]).cross(verts[2] - verts[0]).normalize();
+        let plane = Plane::new(verts[0], normal);
+        Polygon { verts, plane, tag }
+    }
+
+    pub fn centroid(&self) -> WPoint3 {
+        let sum = self
+            .verts
+            .iter()
+            .fold(WPoint3::origin(), |sum, vert| sum + vert.to_vector());
+        sum / self.verts.len() as f64
+    }
+
+    /// Iterates over the edges of the polygon as pairs of corners.
+    pub fn edges(&self) -> impl Iterator<Item = (WPoint3, WPoint3)> + '_ {
+        let len = self.verts.len();
+        (0..len).map(move |i| (self.verts[i], self.verts[(i + 1) % len]))
+    }
+}
+
+impl Shape<WorldSpace> for Polygon {
+    fn hit_by(&self, ray: &Ray) -> Option<HitData> {
+        let hitdata = self.plane.hit_by(ray)?;
+        let hit_point = hitdata.hit_point;
+        let normal = self.plane.normal;
+
+        let mut gtz = true;
+        let mut ltz = true;
+
+        for (v1, v2) in self.edges() {
+            let c = (v2 - v1).cross(hit_point - v1);
+            let nc = normal.dot(c);
+            gtz = gtz && nc > 0.0;
+            ltz = ltz && nc < 0.0;
+            if !gtz && !ltz {
+                return None;
+            }
+        }
+
+        Some(hitdata.with_tag(self.tag))
+    }
+
+    fn paths(&self) -> Vec<LineSegment<WorldSpace>> {
+        self.edges()
+            .map(|(v1, v2)| LineSegment::tagged(v1, v2, self.tag))
+            .collect()
+    }
+
+    fn bounding_box(&self) -> Option<crate::AABB<crate::WorldSpace>> {
+        Some(crate::AABB::from_points(&self.verts))
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        self.plane
+            .validate()
+            .map_err(|_| "its first three corners lie on a line".to_string())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::WVec3;
+
+    #[test]
+    fn test_polygon_hit_by() {
+        // a regular pentagon in the xy plane
+        let verts = (0..5)
+            .map(|i| {
+                let theta = std::f64::consts::TAU * i as f64 / 5.0;
+                WPoint3::new(theta.cos(), theta.sin(), 0.0)
+            })
+            .collect();
+        let pentagon = Polygon::new(verts);
+
+        assert_eq!(
+            pentagon.hit_by(&Ray::new(
+                WPoint3::new(0.0, 0.0, -2.0),
+                WVec3::new(0.0, 0.0, 1.0)
+            )),
+            Some(HitData::new(WPoint3::new(0.0, 0.0, 0.0), 2.0))
+        );
+
+        assert_eq!(
+            pentagon.hit_by(&Ray::new(
+                WPoint3::new(0.9, 0.0, 2.0),
+                WVec3::new(0.0, 0.0, -1.0)
+            )),
+            Some(HitData::new(WPoint3::new(0.9, 0.0, 0.0), 2.0))
+        );
+
+        // outside of the edge between the first two corners
+        assert_eq!(
+            pentagon.hit_by(&Ray::new(
+                WPoint3::new(0.7, 0.5, -2.0),
+                WVec3::new(0.0, 0.0, 1.0)
+            )),
+            None
+        );
+    }
+}
@@ -0,0 +1,394 @@
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Arc;
+
+use super::polygon::Polygon;
+use super::util::orthonormal_basis;
+use crate::bvh::BVHTree;
+use crate::path::LineSegment;
+use crate::{HitData, Ray, Shape, WPoint3, WVec3, WorldSpace, AABB};
+
+/// The golden ratio.
+const PHI: f64 = 1.618_033_988_749_895;
+
+#[derive(Debug)]
+/// A closed solid bounded by flat, convex polygonal faces.
+///
+/// Only the edges between faces are drawn, so faces with more than three corners do not show the
+/// diagonals that a triangulation would.
+pub struct Polyhedron {
+    vertices: Vec<WPoint3>,
+    faces: Vec<Vec<usize>>,
+    aabb: AABB<WorldSpace>,
+    bvh: BVHTree<WorldSpace>,
+    pub tag: usize,
+}
+
+impl Polyhedron {
+    pub fn new(vertices: Vec<WPoint3>, faces: Vec<Vec<usize>>) -> Polyhedron {
+        Self::tagged(vertices, faces, 0)
+    }
+
+    /// Creates a polyhedron from its corners and its faces. Each face lists the indices of its
+    /// corners in winding order.
+    pub fn tagged(vertices: Vec<WPoint3>, faces: Vec<Vec<usize>>, tag: usize) -> Polyhedron {
+        let polygons: Vec<Arc<dyn Shape<WorldSpace>>> = faces
+            .iter()
+            .map(|face| {
+                let verts = face.iter().map(|ndx| vertices[*ndx]).collect();
+                Arc::new(Polygon::tagged(verts, tag)) as Arc<dyn Shape<WorldSpace>>
+            })
+            .collect();
+        let bvh = BVHTree::new(&polygons);
+        let aabb = AABB::from_points(&vertices);
+
+        Polyhedron {
+            vertices,
+            faces,
+            aabb,
+            bvh,
+            tag,
+        }
+    }
+
+    /// A regular tetrahedron whose corners lie on the sphere of the given center and radius.
+    pub fn tetrahedron(center: WPoint3, radius: f64) -> Polyhedron {
+        Self::tetrahedron_tagged(center, radius, 0)
+    }
+
+    pub fn tetrahedron_tagged(center: WPoint3, radius: f64, tag: usize) -> Polyhedron {
+        let vertices = vec![
+            WVec3::new(1.0, 1.0, 1.0),
+            WVec3::new(1.0, -1.0, -1.0),
+            WVec3::new(-1.0, 1.0, -1.0),
+            WVec3::new(-1.0, -1.0, 1.0),
+        ];
+        Self::regular(center, radius, vertices, 8.0f64.sqrt(), tag)
+    }
+
+    /// A regular octahedron whose corners lie on the sphere of the given center and radius.
+    pub fn octahedron(center: WPoint3, radius: f64) -> Polyhedron {
+        Self::octahedron_tagged(center, radius, 0)
+    }
+
+    pub fn octahedron_tagged(center: WPoint3, radius: f64, tag: usize) -> Polyhedron {
+        let vertices = vec![
+            WVec3::new(1.0, 0.0, 0.0),
+            WVec3::new(-1.0, 0.0, 0.0),
+            WVec3::new(0.0, 1.0, 0.0),
+            WVec3::new(0.0, -1.0, 0.0),
+            WVec3::new(0.0, 0.0, 1.0),
+            WVec3::new(0.0, 0.0, -1.0),
+        ];
+        Self::regular(center, radius, vertices, 2.0f64.sqrt(), tag)
+    }
+
+    /// A regular icosahedron whose corners lie on the sphere of the given center and radius.
+    pub fn icosahedron(center: WPoint3, radius: f64) -> Polyhedron {
+        Self::icosahedron_tagged(center, radius, 0)
+    }
+
+    pub fn icosahedron_tagged(center: WPoint3, radius: f64, tag: usize) -> Polyhedron {
+        Self::regular(center, radius, icosahedron_vertices(), 2.0, tag)
+    }
+
+    /// A regular dodecahedron whose corners lie on the sphere of the given center and radius.
+    pub fn dodecahedron(center: WPoint3, radius: f64) -> Polyhedron {
+        Self::dodecahedron_tagged(center, radius, 0)
+    }
+
+    pub fn dodecahedron_tagged(center: WPoint3, radius: f64, tag: usize) -> Polyhedron {
+        // The dodecahedron is the dual of the icosahedron: it has a corner at the middle of each
+        // icosahedron face, and a face around each icosahedron corner.
+        let ico_vertices = icosahedron_vertices();
+        let ico_faces = triangles_by_edge_length(&ico_vertices, 2.0);
+
+        let vertices: Vec<_> = ico_faces
+            .iter()
+            .map(|face| {
+                let sum = face
+                    .iter()
+                    .fold(WVec3::zero(), |sum, ndx| sum + ico_vertices[*ndx]);
+                center + sum.normalize() * radius
+            })
+            .collect();
+
+        let faces = (0..ico_vertices.len())
+            .map(|ico_ndx| {
+                let axis = ico_vertices[ico_ndx].normalize();
+                let (u, v) = orthonormal_basis(axis);
+                let mut around: Vec<_> = ico_faces
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, face)| face.contains(&ico_ndx))
+                    .map(|(face_ndx, _)| face_ndx)
+                    .collect();
+                let angle = |ndx: &usize| {
+                    let dir = vertices[*ndx] - center;
+                    dir.dot(v).atan2(dir.dot(u))
+                };
+                around.sort_by(|a, b| angle(a).total_cmp(&angle(b)));
+                around
+            })
+            .collect();
+
+        let faces = orient_outward(center, &vertices_of(&vertices), faces);
+        Self::tagged(vertices, faces, tag)
+    }
+
+    /// A geodesic sphere made by splitting each face of an icosahedron into `frequency²`
+    /// triangles and pushing their corners out onto the sphere of the given center and radius.
+    pub fn geodesic_sphere(center: WPoint3, radius: f64, frequency: usize) -> Polyhedron {
+        Self::geodesic_sphere_tagged(center, radius, frequency, 0)
+    }
+
+    pub fn geodesic_sphere_tagged(
+        center: WPoint3,
+        radius: f64,
+        frequency: usize,
+        tag: usize,
+    ) -> Polyhedron {
+        let frequency = frequency.max(1);
+        let ico_vertices = icosahedron_vertices();
+        let ico_faces = triangles_by_edge_length(&ico_vertices, 2.0);
+
+        let mut vertices = Vec::new();
+        let mut lookup: HashMap<[i64; 3], usize> = HashMap::new();
+        let mut vertex = |dir: WVec3| {
+            let dir = dir.normalize();
+            let key = dir.to_array().map(|c| (c * 1.0e9).round() as i64);
+            *lookup.entry(key).or_insert_with(|| {
+                vertices.push(center + dir * radius);
+                vertices.len() - 1
+            })
+        };
+
+        let mut faces = Vec::new();
+        for face in &ico_faces {
+            let (a, b, c) = (
+                ico_vertices[face[0]],
+                ico_vertices[face[1]],
+                ico_vertices[face[2]],
+            );
+            let n = frequency as f64;
+            let mut grid = |i: usize, j: usize| {
+                vertex(a + (b - a) * (i as f64 / n) + (c - a) * (j as f64 / n))
+            };
+
+            for i in 0..frequency {
+                for j in 0..(frequency - i) {
+                    faces.push(vec![grid(i, j), grid(i + 1, j), grid(i, j + 1)]);
+                    if i + j + 1 < frequency {
+                        faces.push(vec![grid(i + 1, j), grid(i + 1, j + 1), grid(i, j + 1)]);
+                    }
+                }
+            }
+        }
+
+        let faces = orient_outward(center, &vertices_of(&vertices), faces);
+        Self::tagged(vertices, faces, tag)
+    }
+
+    /// Builds a regular polyhedron with triangular faces from the corners of a canonical one, in
+    /// which every edge has length `edge_length`.
+    fn regular(
+        center: WPoint3,
+        radius: f64,
+        canonical: Vec<WVec3>,
+        edge_length: f64,
+        tag: usize,
+    ) -> Polyhedron {
+        let faces = triangles_by_edge_length(&canonical, edge_length);
+        let faces = orient_outward(WPoint3::origin(), &canonical, faces);
+        let vertices = canonical
+            .iter()
+            .map(|v| center + v.normalize() * radius)
+            .collect();
+        Self::tagged(vertices, faces, tag)
+    }
+
+    pub fn vertices(&self) -> &[WPoint3] {
+        &self.vertices
+    }
+
+    pub fn faces(&self) -> &[Vec<usize>] {
+        &self.faces
+    }
+
+    /// The distinct edges of the polyhedron, as pairs of corner indices.
+    pub fn edges(&self) -> Vec<(usize, usize)> {
+        let mut edges = BTreeSet::new();
+        for face in &self.faces {
+            for i in 0..face.len() {
+                let (a, b) = (face[i], face[(i + 1) % face.len()]);
+                edges.insert((a.min(b), a.max(b)));
+            }
+        }
+        edges.into_iter().collect()
+    }
+}
+
+fn icosahedron_vertices() -> Vec<WVec3> {
+    let mut vertices = Vec::with_capacity(12);
+    for &a in &[-1.0, 1.0] {
+        for &b in &[-PHI, PHI] {
+            vertices.push(WVec3::new(0.0, a, b));
+            vertices.push(WVec3::new(a, b, 0.0));
+            vertices.push(WVec3::new(b, 0.0, a));
+        }
+    }
+    vertices
+}
+
+fn vertices_of(points: &[WPoint3]) -> Vec<WVec3> {
+    points.iter().map(|p| p.to_vector()).collect()
+}
+
+/// Finds every triangle of corners which are all `edge_length` apart from each other.
+fn triangles_by_edge_length(vertices: &[WVec3], edge_length: f64) -> Vec<Vec<usize>> {
+    let adjacent = |a: usize, b: usize| {
+        ((vertices[a] - vertices[b]).length() - edge_length).abs() < 1.0e-6 * edge_length
+    };
+
+    let mut faces = Vec::new();
+    for a in 0..vertices.len() {
+        for b in (a + 1)..vertices.len() {
+            for c in (b + 1)..vertices.len() {
+                if adjacent(a, b) && adjacent(b, c) && adjacent(a, c) {
+                    faces.push(vec![a, b, c]);
+                }
+            }
+        }
+    }
+    faces
+}
+
+/// Reverses the winding of any face of a convex solid whose normal points towards its center.
+fn orient_outward(center: WPoint3, vertices: &[WVec3], faces: Vec<Vec<usize>>) -> Vec<Vec<usize>> {
+    faces
+        .into_iter()
+        .map(|mut face| {
+            let (a, b, c) = (vertices[face[0]], vertices[face[1]], vertices[face[2]]);
+            let normal = (b - a).cross(c - a);
+            if normal.dot(a - center.to_vector()) < 0.0 {
+                face.reverse();
+            }
+            face
+        })
+        .collect()
+}
+
+impl Shape<WorldSpace> for Polyhedron {
+    fn hit_by(&self, ray: &Ray) -> Option<HitData> {
+        // The faces are an implementation detail, so the hit is reported as being on the
+        // polyhedron as a whole.
+        self.bvh.closest_hit(*ray).map(|hit| HitData {
+            shape_id: None,
+            tag: self.tag,
+            ..hit
+        })
+    }
+
+    fn paths(&self) -> Vec<LineSegment<WorldSpace>> {
+        self.edges()
+            .into_iter()
+            .map(|(a, b)| LineSegment::tagged(self.vertices[a], self.vertices[b], self.tag))
+            .collect()
+    }
+
+    fn bounding_box(&self) -> Option<crate::AABB<crate::WorldSpace>> {
+        Some(self.aabb)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn assert_closed(poly: &Polyhedron, vertices: usize, edges: usize, faces: usize) {
+        assert_eq!(poly.vertices().len(), vertices);
+        assert_eq!(poly.edges().len(), edges);
+        assert_eq!(poly.faces().len(), faces);
+        // Euler's formula for polyhedra which are topologically spheres
+        assert_eq!(vertices + faces, edges + 2);
+    }
+
+    #[test]
+    fn test_polyhedron_topology() {
+        let origin = WPoint3::origin();
+        assert_closed(&Polyhedron::tetrahedron(origin, 1.0), 4, 6, 4);
+        assert_closed(&Polyhedron::octahedron(origin, 1.0), 6, 12, 8);
+        assert_closed(&Polyhedron::icosahedron(origin, 1.0), 12, 30, 20);
+        assert_closed(&Polyhedron::dodecahedron(origin, 1.0), 20, 30, 12);
+        assert_closed(&Polyhedron::geodesic_sphere(origin, 1.0, 1), 12, 30, 20);
+        assert_closed(&Polyhedron::geodesic_sphere(origin, 1.0, 2), 42, 120, 80);
+        assert_closed(&Polyhedron::geodesic_sphere(origin, 1.0, 3), 92, 270, 180);
+
+        let dodecahedron = Polyhedron::dodecahedron(origin, 1.0);
+        assert!(dodecahedron.faces().iter().all(|face| face.len() == 5));
+    }
+
+    #[test]
+    fn test_polyhedron_hit_by() {
+        let octahedron = Polyhedron::octahedron(WPoint3::new(0.0, 0.0, 1.0), 1.0);
+
+        assert_eq!(
+            octahedron.hit_by(&Ray::new(
+                WPoint3::new(0.1, 0.05, 3.0),
+                WVec3::new(0.0, 0.0, -1.0)
+            )),
+            Some(HitData::new(WPoint3::new(0.1, 0.05, 1.85), 1.15))
+        );
+
+        // hits the middle of a face
+        let face_center = WPoint3::new(1.0 / 3.0, 1.0 / 3.0, 1.0 + 1.0 / 3.0);
+        assert_eq!(
+            octahedron.hit_by(&Ray::normalize_new(
+                WPoint3::new(1.0, 1.0, 2.0),
+                WVec3::new(-1.0, -1.0, -1.0)
+            )),
+            Some(HitData::new(
+                face_center,
+                (face_center - WPoint3::new(1.0, 1.0, 2.0)).length()
+            ))
+        );
+
+        assert_eq!(
+            octahedron.hit_by(&Ray::new(
+                WPoint3::new(0.6, 0.6, 3.0),
+                WVec3::new(0.0, 0.0, -1.0)
+            )),
+            None
+        );
+
+        // towards the middle of a face of the dodecahedron
+        let dodecahedron = Polyhedron::dodecahedron(WPoint3::origin(), 1.0);
+        let dir = WVec3::new(0.0, 1.0, PHI).normalize();
+        // the inradius of a dodecahedron with a circumradius of 1
+        let inradius = 0.794_654_472_291_766;
+        assert_eq!(
+            dodecahedron.hit_by(&Ray::new(WPoint3::origin() - dir * 3.0, dir)),
+            Some(HitData::new(
+                WPoint3::origin() - dir * inradius,
+                3.0 - inradius
+            ))
+        );
+    }
+
+    #[test]
+    fn test_tagged() {
+        let origin = WPoint3::origin();
+        let polyhedra = [
+            Polyhedron::tetrahedron_tagged(origin, 1.0, 1),
+            Polyhedron::octahedron_tagged(origin, 1.0, 2),
+            Polyhedron::icosahedron_tagged(origin, 1.0, 3),
+            Polyhedron::dodecahedron_tagged(origin, 1.0, 4),
+            Polyhedron::geodesic_sphere_tagged(origin, 1.0, 2, 5),
+        ];
+        for (poly, tag) in polyhedra.iter().zip(1..) {
+            let ray = Ray::new(WPoint3::new(0.1, 0.2, 3.0), WVec3::new(0.0, 0.0, -1.0));
+            assert_eq!(poly.hit_by(&ray).unwrap().tag, tag);
+            assert!(poly.paths().iter().all(|path| path.tag == tag));
+        }
+        assert_eq!(Polyhedron::octahedron(origin, 1.0).tag, 0);
+    }
+}
@@ -42,19 +42,42 @@ impl Shape<WorldSpace> for RectPrism {
             Some(p) => {
                 let wp = WPoint3::new(p.x, p.y, p.z);
                 let dist = (wp - ray.point).length();
-                Some(HitData::new(wp, dist))
+
+                // The face which was hit is the one the hit point lies closest to.
+                let (min, max) = (self.min.to_array(), self.max.to_array());
+                let hit = wp.to_array();
+                let (axis, sign, _) = (0..3)
+                    .flat_map(|axis| {
+                        [
+                            (axis, -1.0, (hit[axis] - min[axis]).abs()),
+                            (axis, 1.0, (hit[axis] - max[axis]).abs()),
+                        ]
+                    })
+                    .fold((0, -1.0, f64::INFINITY), |best, face| {
+                        if face.2 < best.2 {
+                            face
+                        } else {
+                            best
+                        }
+                    });
+                let mut normal = [0.0; 3];
+                normal[axis] = sign;
+
+                let uv = |a: usize| (hit[a] - min[a]) / (max[a] - min[a]);
+                Some(
+                    HitData::new(wp, dist)
+                        .with_normal(WVec3::from(normal))
+                        .with_tag(self.tag)
+                        .with_uv(uv((axis + 1) % 3), uv((axis + 2) % 3)),
+                )
             }
             None => None,
         }
     }
 
     fn paths(&self) -> Vec<LineSegment<WorldSpace>> {
-        let expand = (self.max - self.min).normalize() * 0.0015;
-        let pathmin = self.min - expand;
-        let pathmax = self.max + expand;
-
-        let (x1, y1, z1) = (pathmin.x, pathmin.y, pathmin.z);
-        let (x2, y2, z2) = (pathmax.x, pathmax.y, pathmax.z);
+        let (x1, y1, z1) = (self.min.x, self.min.y, self.min.z);
+        let (x2, y2, z2) = (self.max.x, self.max.y, self.max.z);
 
         let p1 = WPoint3::new(x1, y1, z1);
         let p2 = WPoint3::new(x2, y1, z1);
@@ -114,5 +137,14 @@ mod test {
                 12.241323457861899
             ))
         );
+
+        assert_eq!(
+            prism1.hit_by(&ray1).map(|hit| hit.normal),
+            Some(WVec3::new(-1.0, 0.0, 0.0))
+        );
+        assert_eq!(
+            prism1.hit_by(&ray2).map(|hit| hit.normal),
+            Some(WVec3::new(0.0, 1.0, 0.0))
+        );
     }
 }
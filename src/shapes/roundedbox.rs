@@ -0,0 +1,343 @@
+use super::util::{
+    closest_hit, cylinder_silhouette, quarter_arc, ray_cylinder, ray_sphere,
+    sphere_normals_to_paths, sphere_silhouette,
+};
+use crate::path::LineSegment;
+use crate::{HitData, Ray, Shape, WPoint3, WVec3, WorldSpace};
+
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(test, derive(PartialEq))]
+/// An axis-aligned box whose edges and corners are rounded off with a given radius.
+pub struct RoundedBox {
+    pub min: WVec3,
+    pub max: WVec3,
+    /// The radius of the rounded edges. This is clamped to half of the box's smallest extent.
+    pub radius: f64,
+    pub tag: usize,
+}
+
+impl RoundedBox {
+    pub fn new(min: WVec3, max: WVec3, radius: f64) -> RoundedBox {
+        Self::tagged(min, max, radius, 0)
+    }
+
+    pub fn tagged(min: WVec3, max: WVec3, radius: f64, tag: usize) -> RoundedBox {
+        let extent = max - min;
+        let radius = radius
+            .max(0.0)
+            .min(extent.x / 2.0)
+            .min(extent.y / 2.0)
+            .min(extent.z / 2.0);
+        RoundedBox {
+            min,
+            max,
+            radius,
+            tag,
+        }
+    }
+
+    /// The box swept by the centers of the rounded edges.
+    fn inner(&self) -> ([f64; 3], [f64; 3]) {
+        let r = WVec3::splat(self.radius);
+        ((self.min + r).to_array(), (self.max - r).to_array())
+    }
+
+    /// Lists the rounded edges as `(axis, other axes, signs of the other axes)`.
+    fn edges() -> Vec<(usize, [usize; 2], [f64; 2])> {
+        let mut edges = Vec::with_capacity(12);
+        for axis in 0..3 {
+            let others = [(axis + 1) % 3, (axis + 2) % 3];
+            for signs in &[[-1.0, -1.0], [-1.0, 1.0], [1.0, -1.0], [1.0, 1.0]] {
+                edges.push((axis, others, *signs));
+            }
+        }
+        edges
+    }
+
+    /// Lists the signs of each axis of the eight rounded corners.
+    fn corners() -> Vec<[f64; 3]> {
+        let mut corners = Vec::with_capacity(8);
+        for &x in &[-1.0, 1.0] {
+            for &y in &[-1.0, 1.0] {
+                for &z in &[-1.0, 1.0] {
+                    corners.push([x, y, z]);
+                }
+            }
+        }
+        corners
+    }
+}
+
+fn pick(imin: &[f64; 3], imax: &[f64; 3], axis: usize, sign: f64) -> f64 {
+    if sign < 0.0 {
+        imin[axis]
+    } else {
+        imax[axis]
+    }
+}
+
+fn unit(axis: usize) -> WVec3 {
+    let mut v = [0.0; 3];
+    v[axis] = 1.0;
+    WVec3::from(v)
+}
+
+impl Shape<WorldSpace> for RoundedBox {
+    fn hit_by(&self, ray: &Ray) -> Option<HitData> {
+        let (imin, imax) = self.inner();
+        let (min, max) = (self.min.to_array(), self.max.to_array());
+        let origin = ray.point.to_array();
+        let dir = ray.dir.to_array();
+        let within = |p: &[f64; 3], axis: usize| p[axis] >= imin[axis] && p[axis] <= imax[axis];
+        let at = |t: f64| (ray.point + ray.dir * t).to_array();
+
+        let mut candidates = Vec::new();
+
+        // flat faces
+        for axis in 0..3 {
+            if dir[axis] == 0.0 {
+                continue;
+            }
+            for (side, sign) in [(min[axis], -1.0), (max[axis], 1.0)] {
+                let t = (side - origin[axis]) / dir[axis];
+                let p = at(t);
+                if within(&p, (axis + 1) % 3) && within(&p, (axis + 2) % 3) {
+                    candidates.push((t, unit(axis) * sign));
+                }
+            }
+        }
+
+        // rounded edges
+        for (axis, others, signs) in Self::edges() {
+            let mut base = [0.0; 3];
+            base[axis] = imin[axis];
+            base[others[0]] = pick(&imin, &imax, others[0], signs[0]);
+            base[others[1]] = pick(&imin, &imax, others[1], signs[1]);
+
+            if let Some((t0, t1)) = ray_cylinder(ray, WPoint3::from(base), unit(axis), self.radius)
+            {
+                for t in [t0, t1] {
+                    let p = at(t);
+                    let outward =
+                        (0..2).all(|i| (p[others[i]] - base[others[i]]) * signs[i] >= 0.0);
+                    if within(&p, axis) && outward {
+                        let mut normal = [0.0; 3];
+                        for &other in &others {
+                            normal[other] = (p[other] - base[other]) / self.radius;
+                        }
+                        candidates.push((t, WVec3::from(normal)));
+                    }
+                }
+            }
+        }
+
+        // rounded corners
+        for signs in Self::corners() {
+            let center = [0, 1, 2].map(|axis| pick(&imin, &imax, axis, signs[axis]));
+            if let Some((t0, t1)) = ray_sphere(ray, WPoint3::from(center), self.radius) {
+                for t in [t0, t1] {
+                    let p = at(t);
+                    if (0..3).all(|axis| (p[axis] - center[axis]) * signs[axis] >= 0.0) {
+                        let normal = (WPoint3::from(p) - WPoint3::from(center)) / self.radius;
+                        candidates.push((t, normal));
+                    }
+                }
+            }
+        }
+
+        closest_hit(candidates).map(|(t, normal)| {
+            HitData::new(ray.point + ray.dir * t, t)
+                .with_normal(normal)
+                .with_tag(self.tag)
+        })
+    }
+
+    fn paths(&self) -> Vec<LineSegment<WorldSpace>> {
+        let (imin, imax) = self.inner();
+        let (min, max) = (self.min.to_array(), self.max.to_array());
+        let mut paths = Vec::new();
+
+        // outlines of the flat faces, where they meet the rounded edges
+        for axis in 0..3 {
+            let others = [(axis + 1) % 3, (axis + 2) % 3];
+            for side in [min[axis], max[axis]] {
+                for (along, across) in [(others[0], others[1]), (others[1], others[0])] {
+                    for edge in [imin[across], imax[across]] {
+                        let mut p1 = [0.0; 3];
+                        p1[axis] = side;
+                        p1[across] = edge;
+                        p1[along] = imin[along];
+                        let mut p2 = p1;
+                        p2[along] = imax[along];
+                        if p1 != p2 {
+                            paths.push(LineSegment::tagged(
+                                WPoint3::from(p1),
+                                WPoint3::from(p2),
+                                self.tag,
+                            ));
+                        }
+                    }
+                }
+            }
+        }
+
+        if self.radius == 0.0 {
+            return paths;
+        }
+
+        // arcs at both ends of each rounded edge, which outline the rounded corners
+        for (axis, others, signs) in Self::edges() {
+            for end in [imin[axis], imax[axis]] {
+                let mut center = [0.0; 3];
+                center[axis] = end;
+                center[others[0]] = pick(&imin, &imax, others[0], signs[0]);
+                center[others[1]] = pick(&imin, &imax, others[1], signs[1]);
+                paths.extend(quarter_arc(
+                    WPoint3::from(center),
+                    self.radius,
+                    unit(others[0]) * signs[0],
+                    unit(others[1]) * signs[1],
+                    self.tag,
+                ));
+            }
+        }
+
+        paths
+    }
+
+    fn silhouette_paths(&self, eye: WPoint3) -> Vec<LineSegment<WorldSpace>> {
+        if self.radius == 0.0 {
+            return Vec::new();
+        }
+
+        let (imin, imax) = self.inner();
+        let mut paths = Vec::new();
+
+        for (axis, others, signs) in Self::edges() {
+            let mut start = [0.0; 3];
+            start[axis] = imin[axis];
+            start[others[0]] = pick(&imin, &imax, others[0], signs[0]);
+            start[others[1]] = pick(&imin, &imax, others[1], signs[1]);
+            let start = WPoint3::from(start);
+            let end = start + unit(axis) * (imax[axis] - imin[axis]);
+
+            for normal in cylinder_silhouette(start, unit(axis), self.radius, eye) {
+                let n = normal.to_array();
+                if (0..2).all(|i| n[others[i]] * signs[i] >= 0.0) {
+                    let offset = normal * self.radius;
+                    paths.push(LineSegment::tagged(start + offset, end + offset, self.tag));
+                }
+            }
+        }
+
+        for signs in Self::corners() {
+            let center = WPoint3::from([0, 1, 2].map(|axis| pick(&imin, &imax, axis, signs[axis])));
+            let normals = sphere_silhouette(center, self.radius, eye);
+            paths.extend(sphere_normals_to_paths(
+                center,
+                self.radius,
+                &normals,
+                |n| (0..3).all(|axis| n.to_array()[axis] * signs[axis] >= 0.0),
+                self.tag,
+            ));
+        }
+
+        paths
+    }
+
+    fn bounding_box(&self) -> Option<crate::AABB<crate::WorldSpace>> {
+        Some(crate::AABB::new(self.min.to_point(), self.max.to_point()))
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        let extent = self.max - self.min;
+        if extent.to_array().iter().all(|e| *e > 0.0) {
+            Ok(())
+        } else {
+            Err(format!(
+                "its extent of {:?} isn't positive along every axis",
+                extent.to_array()
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_validate() {
+        let rbox = |max| RoundedBox::new(WVec3::zero(), max, 0.5).validate();
+        assert!(rbox(WVec3::new(2.0, 1.0, 1.0)).is_ok());
+        assert!(rbox(WVec3::new(2.0, 0.0, 1.0))
+            .unwrap_err()
+            .contains("extent"));
+    }
+
+    #[test]
+    fn test_rounded_box_hit_by() {
+        let rbox = RoundedBox::new(WVec3::new(0.0, 0.0, 0.0), WVec3::new(2.0, 2.0, 2.0), 0.5);
+
+        // flat face
+        assert_eq!(
+            rbox.hit_by(&Ray::new(
+                WPoint3::new(-1.0, 1.0, 1.0),
+                WVec3::new(1.0, 0.0, 0.0)
+            )),
+            Some(HitData::new(WPoint3::new(0.0, 1.0, 1.0), 1.0))
+        );
+
+        // rounded edge, hit along its bisector
+        let edge_point = WPoint3::new(0.5, 0.5, 1.0) - WVec3::new(1.0, 1.0, 0.0).normalize() * 0.5;
+        assert_eq!(
+            rbox.hit_by(&Ray::normalize_new(
+                WPoint3::new(-1.0, -1.0, 1.0),
+                WVec3::new(1.0, 1.0, 0.0)
+            )),
+            Some(HitData::new(
+                edge_point,
+                (edge_point - WPoint3::new(-1.0, -1.0, 1.0)).length()
+            ))
+        );
+
+        // rounded corner, hit along the diagonal
+        let corner_point = WPoint3::splat(0.5) - WVec3::splat(1.0).normalize() * 0.5;
+        assert_eq!(
+            rbox.hit_by(&Ray::normalize_new(WPoint3::splat(-1.0), WVec3::splat(1.0))),
+            Some(HitData::new(
+                corner_point,
+                (corner_point - WPoint3::splat(-1.0)).length()
+            ))
+        );
+
+        // grazes the rounded edge
+        let x = 0.5 - (0.25f64 - 0.45 * 0.45).sqrt();
+        assert_eq!(
+            rbox.hit_by(&Ray::new(
+                WPoint3::new(-1.0, 0.05, 1.0),
+                WVec3::new(1.0, 0.0, 0.0)
+            )),
+            Some(HitData::new(WPoint3::new(x, 0.05, 1.0), 1.0 + x))
+        );
+
+        // passes by the corner which a sharp box would have had
+
+        assert_eq!(
+            rbox.hit_by(&Ray::normalize_new(
+                WPoint3::new(-1.0, 0.01, 0.01),
+                WVec3::new(1.0, 0.0, 0.0)
+            )),
+            None
+        );
+
+        // from the inside
+        assert_eq!(
+            rbox.hit_by(&Ray::new(
+                WPoint3::new(1.0, 1.0, 1.0),
+                WVec3::new(0.0, 0.0, 1.0)
+            )),
+            Some(HitData::new(WPoint3::new(1.0, 1.0, 2.0), 1.0))
+        );
+    }
+}
@@ -11,15 +11,21 @@ pub struct Sphere {
     radius: f64,
     /// Precomputed radius squared.
     radius2: f64,
+    tag: usize,
 }
 
 impl Sphere {
     pub fn new(center: WPoint3, radius: f64) -> Sphere {
+        Self::tagged(center, radius, 0)
+    }
+
+    pub fn tagged(center: WPoint3, radius: f64, tag: usize) -> Sphere {
         let radius2 = radius * radius;
         Sphere {
             center,
             radius,
             radius2,
+            tag,
         }
     }
 }
@@ -46,7 +52,17 @@ impl Shape<WorldSpace> for Sphere {
         let t = if t_0 < 0.0 { t_1 } else { t_0 };
 
         let hit_point = ray.point + (ray.dir.normalize() * t);
-        Some(HitData::new(hit_point, t))
+        let normal = (hit_point - self.center) / self.radius;
+
+        // longitude and latitude, each scaled to lie between 0 and 1
+        let u = 0.5 + normal.y.atan2(normal.x) / std::f64::consts::TAU;
+        let v = normal.z.clamp(-1.0, 1.0).acos() / std::f64::consts::PI;
+        Some(
+            HitData::new(hit_point, t)
+                .with_normal(normal)
+                .with_uv(u, v)
+                .with_tag(self.tag),
+        )
     }
 
     fn paths(&self) -> Vec<LineSegment<WorldSpace>> {
@@ -58,12 +74,21 @@ impl Shape<WorldSpace> for Sphere {
         let max = self.center + WVec3::splat(self.radius);
         Some(crate::AABB::new(min, max))
     }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.radius > 0.0 {
+            Ok(())
+        } else {
+            Err(format!("its radius of {} isn't positive", self.radius))
+        }
+    }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
     use crate::{WPoint3, WVec3};
+    use euclid::approxeq::ApproxEq;
 
     #[test]
     fn test_hit_by() {
@@ -121,4 +146,31 @@ mod test {
             Some(HitData::new(WPoint3::new(-1.0, 0.0, 0.0), 1.0,))
         );
     }
+
+    #[test]
+    fn test_hit_normal_and_uv() {
+        let sphere = Sphere::new(WPoint3::new(1.0, 0.0, 0.0), 0.5);
+
+        let hit = sphere
+            .hit_by(&Ray::new(
+                WPoint3::new(1.0, 0.0, 2.0),
+                WVec3::new(0.0, 0.0, -1.0),
+            ))
+            .unwrap();
+        assert!(hit.normal.approx_eq(&WVec3::new(0.0, 0.0, 1.0)));
+        assert_eq!(hit.uv.map(|(_, v)| v), Some(0.0));
+
+        let hit = sphere
+            .hit_by(&Ray::new(
+                WPoint3::new(0.0, 0.0, 0.0),
+                WVec3::new(1.0, 0.0, 0.0),
+            ))
+            .unwrap();
+        assert!(hit.normal.approx_eq(&WVec3::new(-1.0, 0.0, 0.0)));
+        assert_eq!(hit.uv, Some((1.0, 0.5)));
+
+        let tagged = Sphere::tagged(WPoint3::new(1.0, 0.0, 0.0), 0.5, 3);
+        let hit = tagged.hit_by(&Ray::new(WPoint3::origin(), WVec3::new(1.0, 0.0, 0.0)));
+        assert_eq!(hit.map(|hit| hit.tag), Some(3));
+    }
 }
@@ -53,7 +53,13 @@ impl Shape<WorldSpace> for Triangle {
                     }
                 }
 
-                Some(hitdata)
+                // barycentric coordinates of the hit relative to the second and third corners
+                let area = self.edges[0].cross(-self.edges[2]).dot(normal);
+                let to_hit = hit_point - self.verts[0];
+                let u = to_hit.cross(-self.edges[2]).dot(normal) / area;
+                let v = self.edges[0].cross(to_hit).dot(normal) / area;
+
+                Some(hitdata.with_tag(self.tag).with_uv(u, v))
             }
             None => None,
         }
@@ -64,11 +70,6 @@ impl Shape<WorldSpace> for Triangle {
         let v1 = self.verts[1];
         let v2 = self.verts[2];
 
-        let centroid = (v0 + v1.to_vector() + v2.to_vector()) / 3.0;
-        let v0 = v0 + (v0 - centroid).normalize() * 0.015;
-        let v1 = v1 + (v1 - centroid).normalize() * 0.015;
-        let v2 = v2 + (v2 - centroid).normalize() * 0.015;
-
         vec![
             LineSegment::tagged(v0, v1, self.tag),
             LineSegment::tagged(v1, v2, self.tag),
@@ -92,6 +93,12 @@ impl Shape<WorldSpace> for Triangle {
 
         Some(crate::AABB::new(min, max))
     }
+
+    fn validate(&self) -> Result<(), String> {
+        self.plane
+            .validate()
+            .map_err(|_| "its corners lie on a line".to_string())
+    }
 }
 
 #[cfg(test)]
@@ -143,4 +150,24 @@ mod test {
             Some(HitData::new(WPoint3::new(0.1, 0.01, 0.0), 2.0))
         );
     }
+
+    #[test]
+    fn test_tri_hit_uv() {
+        let tri = Triangle::tagged(
+            WPoint3::new(0.0, 0.0, 0.0),
+            WPoint3::new(2.0, 0.0, 0.0),
+            WPoint3::new(0.0, 2.0, 0.0),
+            3,
+        );
+
+        let hit = tri
+            .hit_by(&Ray::new(
+                WPoint3::new(0.5, 1.0, -2.0),
+                WVec3::new(0.0, 0.0, 1.0),
+            ))
+            .unwrap();
+        assert_eq!(hit.tag, 3);
+        assert_eq!(hit.uv, Some((0.25, 0.5)));
+        assert_eq!(hit.normal.z.abs(), 1.0);
+    }
 }
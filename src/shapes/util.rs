@@ -0,0 +1,160 @@
+//! Intersection and outline helpers shared by the curved shapes.
+use crate::path::LineSegment;
+use crate::{Ray, WPoint3, WVec3, WorldSpace};
+
+/// How many segments are used to approximate a full circle.
+pub(crate) const CIRCLE_SEGMENTS: usize = 64;
+
+/// The radius at which to place the vertices of a polyline approximating a circle of the given
+/// radius, so that the polyline's segments touch the circle instead of cutting beneath the surface
+/// it lies on.
+pub(crate) fn polyline_radius(radius: f64) -> f64 {
+    radius / (std::f64::consts::PI / CIRCLE_SEGMENTS as f64).cos()
+}
+
+/// Finds the distances along `ray` at which it crosses the surface of a sphere, if any.
+pub(crate) fn ray_sphere(ray: &Ray, center: WPoint3, radius: f64) -> Option<(f64, f64)> {
+    let l_vec = center - ray.point;
+    let t_ca = l_vec.dot(ray.dir);
+    let d2 = l_vec.dot(l_vec) - t_ca.powi(2);
+    let radius2 = radius * radius;
+
+    if d2 >= radius2 {
+        return None;
+    }
+
+    let t_hc = (radius2 - d2).sqrt();
+    Some((t_ca - t_hc, t_ca + t_hc))
+}
+
+/// Finds the distances along `ray` at which it crosses the surface of an infinite cylinder, if
+/// any. `axis` must be normalized.
+pub(crate) fn ray_cylinder(
+    ray: &Ray,
+    base: WPoint3,
+    axis: WVec3,
+    radius: f64,
+) -> Option<(f64, f64)> {
+    let oc = ray.point - base;
+    let dp = ray.dir - axis * ray.dir.dot(axis);
+    let op = oc - axis * oc.dot(axis);
+
+    let a = dp.dot(dp);
+    if a == 0.0 {
+        return None;
+    }
+    let b = 2.0 * dp.dot(op);
+    let c = op.dot(op) - radius * radius;
+
+    let disc = b * b - 4.0 * a * c;
+    if disc < 0.0 {
+        return None;
+    }
+
+    let sq = disc.sqrt();
+    Some(((-b - sq) / (2.0 * a), (-b + sq) / (2.0 * a)))
+}
+
+/// Picks the closest candidate hit with a non-negative distance, along with what is known about it.
+pub(crate) fn closest_hit<T>(candidates: impl IntoIterator<Item = (f64, T)>) -> Option<(f64, T)> {
+    candidates
+        .into_iter()
+        .filter(|(t, _)| *t >= 0.0)
+        .fold(None, |best, (t, data)| match best {
+            Some((b, best_data)) if b <= t => Some((b, best_data)),
+            _ => Some((t, data)),
+        })
+}
+
+/// Returns two unit vectors which form an orthonormal basis together with `w`.
+pub(crate) fn orthonormal_basis(w: WVec3) -> (WVec3, WVec3) {
+    let a = if w.x.abs() < 0.9 {
+        WVec3::new(1.0, 0.0, 0.0)
+    } else {
+        WVec3::new(0.0, 1.0, 0.0)
+    };
+    let u = w.cross(a).normalize();
+    let v = w.cross(u);
+    (u, v)
+}
+
+/// Finds the surface normals of a cylinder along which its silhouette lines lie as seen from
+/// `eye`. `axis` must be normalized.
+pub(crate) fn cylinder_silhouette(
+    base: WPoint3,
+    axis: WVec3,
+    radius: f64,
+    eye: WPoint3,
+) -> Vec<WVec3> {
+    let w = eye - base;
+    let d = w - axis * w.dot(axis);
+    let dist = d.length();
+    if dist <= radius {
+        return Vec::new();
+    }
+
+    let u = d / dist;
+    let v = axis.cross(u);
+    let cos = radius / dist;
+    let sin = (1.0 - cos * cos).sqrt();
+    vec![u * cos + v * sin, u * cos - v * sin]
+}
+
+/// Finds the surface normals of a sphere along which its silhouette circle lies as seen from
+/// `eye`. The returned normals form a closed loop.
+pub(crate) fn sphere_silhouette(center: WPoint3, radius: f64, eye: WPoint3) -> Vec<WVec3> {
+    let w = eye - center;
+    let dist = w.length();
+    if dist <= radius {
+        return Vec::new();
+    }
+
+    let w = w / dist;
+    let (u, v) = orthonormal_basis(w);
+    let cos = radius / dist;
+    let sin = (1.0 - cos * cos).sqrt();
+    (0..=CIRCLE_SEGMENTS)
+        .map(|i| {
+            let phi = std::f64::consts::TAU * i as f64 / CIRCLE_SEGMENTS as f64;
+            w * cos + (u * phi.cos() + v * phi.sin()) * sin
+        })
+        .collect()
+}
+
+/// Turns a loop of normals on a sphere into line segments, keeping only the portions for which
+/// `keep` holds at both ends.
+pub(crate) fn sphere_normals_to_paths(
+    center: WPoint3,
+    radius: f64,
+    normals: &[WVec3],
+    keep: impl Fn(WVec3) -> bool,
+    tag: usize,
+) -> Vec<LineSegment<WorldSpace>> {
+    let radius = polyline_radius(radius);
+    normals
+        .windows(2)
+        .filter(|pair| keep(pair[0]) && keep(pair[1]))
+        .map(|pair| LineSegment::tagged(center + pair[0] * radius, center + pair[1] * radius, tag))
+        .collect()
+}
+
+/// Approximates the arc of a circle from direction `from` to direction `to` with line segments.
+/// The directions must be perpendicular unit vectors.
+pub(crate) fn quarter_arc(
+    center: WPoint3,
+    radius: f64,
+    from: WVec3,
+    to: WVec3,
+    tag: usize,
+) -> Vec<LineSegment<WorldSpace>> {
+    let segments = CIRCLE_SEGMENTS / 4;
+    let radius = polyline_radius(radius);
+    let point = |i: usize| {
+        let theta = std::f64::consts::FRAC_PI_2 * i as f64 / segments as f64;
+        let n = from * theta.cos() + to * theta.sin();
+        center + n * radius
+    };
+    (0..segments)
+        .map(|i| LineSegment::tagged(point(i), point(i + 1), tag))
+        .collect()
+}
@@ -0,0 +1,415 @@
+use std::collections::{BTreeMap, HashSet};
+
+use crate::path::LineSegment;
+use crate::{HitData, Ray, Shape, WPoint3, WVec3, WorldSpace, AABB};
+
+#[derive(Debug, Clone)]
+#[cfg_attr(test, derive(PartialEq))]
+/// A regular grid of solid or empty cubic cells.
+///
+/// Rays are traced through the grid cell by cell, and only the outline of the grid's exterior
+/// surface is drawn: edges between neighboring filled cells, and between neighboring faces which
+/// lie in the same plane, are left out.
+pub struct VoxelGrid {
+    /// The corner of the grid with the lowest coordinates.
+    pub origin: WPoint3,
+    /// The size of a single cell along each axis.
+    pub voxel_size: WVec3,
+    dims: [usize; 3],
+    cells: Vec<bool>,
+    pub tag: usize,
+}
+
+impl VoxelGrid {
+    pub fn new(origin: WPoint3, voxel_size: WVec3, dims: [usize; 3]) -> VoxelGrid {
+        Self::tagged(origin, voxel_size, dims, 0)
+    }
+
+    /// Creates an empty grid with `dims` cells along each axis.
+    pub fn tagged(origin: WPoint3, voxel_size: WVec3, dims: [usize; 3], tag: usize) -> VoxelGrid {
+        VoxelGrid {
+            origin,
+            voxel_size,
+            dims,
+            cells: vec![false; dims[0] * dims[1] * dims[2]],
+            tag,
+        }
+    }
+
+    pub fn dims(&self) -> [usize; 3] {
+        self.dims
+    }
+
+    pub fn set(&mut self, voxel: [usize; 3], filled: bool) {
+        let ndx = self.index(voxel);
+        self.cells[ndx] = filled;
+    }
+
+    pub fn is_filled(&self, voxel: [usize; 3]) -> bool {
+        self.cells[self.index(voxel)]
+    }
+
+    fn index(&self, voxel: [usize; 3]) -> usize {
+        assert!(
+            (0..3).all(|axis| voxel[axis] < self.dims[axis]),
+            "Voxel {:?} is outside of a grid of size {:?}",
+            voxel,
+            self.dims
+        );
+        voxel[0] + self.dims[0] * (voxel[1] + self.dims[1] * voxel[2])
+    }
+
+    /// Like `is_filled`, but treats everything outside of the grid as empty.
+    fn filled_at(&self, voxel: [isize; 3]) -> bool {
+        let inside =
+            (0..3).all(|axis| voxel[axis] >= 0 && (voxel[axis] as usize) < self.dims[axis]);
+        inside && self.is_filled(voxel.map(|c| c as usize))
+    }
+
+    /// Whether the given cell has an exposed face pointing along `axis` in the direction `step`.
+    fn exterior_face(&self, voxel: [isize; 3], axis: usize, step: isize) -> bool {
+        let mut neighbor = voxel;
+        neighbor[axis] += step;
+        self.filled_at(voxel) && !self.filled_at(neighbor)
+    }
+
+    /// Builds the hit data for a ray striking the face of a cell which points along `axis` in the
+    /// direction `sign`.
+    fn hit_data(&self, point: WPoint3, dist: f64, axis: usize, sign: f64) -> HitData {
+        let mut normal = [0.0; 3];
+        normal[axis] = sign;
+        HitData::new(point, dist)
+            .with_normal(WVec3::from(normal))
+            .with_tag(self.tag)
+    }
+
+    fn lattice_point(&self, point: [isize; 3]) -> WPoint3 {
+        let size = self.voxel_size.to_array();
+        let offset = [0, 1, 2].map(|axis| point[axis] as f64 * size[axis]);
+        self.origin + WVec3::from(offset)
+    }
+}
+
+impl Shape<WorldSpace> for VoxelGrid {
+    fn hit_by(&self, ray: &Ray) -> Option<HitData> {
+        // The traversal below would never leave the starting cell.
+        if !ray.has_direction() {
+            return None;
+        }
+        let origin = ray.point.to_array();
+        let dir = ray.dir.to_array();
+        let grid_min = self.origin.to_array();
+        let size = self.voxel_size.to_array();
+
+        // Find where the ray enters and leaves the grid as a whole.
+        let mut t_enter = f64::NEG_INFINITY;
+        let mut enter_axis = 0;
+        let mut t_exit = f64::INFINITY;
+        for axis in 0..3 {
+            let lo = grid_min[axis];
+            let hi = lo + size[axis] * self.dims[axis] as f64;
+            if dir[axis] == 0.0 {
+                if origin[axis] < lo || origin[axis] > hi {
+                    return None;
+                }
+                continue;
+            }
+            let t1 = (lo - origin[axis]) / dir[axis];
+            let t2 = (hi - origin[axis]) / dir[axis];
+            if t1.min(t2) > t_enter {
+                t_enter = t1.min(t2);
+                enter_axis = axis;
+            }
+            t_exit = t_exit.min(t1.max(t2));
+        }
+        if t_exit < t_enter || t_exit < 0.0 {
+            return None;
+        }
+
+        let started_outside = t_enter > 0.0;
+        let t_start = t_enter.max(0.0);
+        let start = ray.point + ray.dir * t_start;
+
+        let mut voxel = [0isize; 3];
+        let mut step = [0isize; 3];
+        let mut t_max = [f64::INFINITY; 3];
+        let mut t_delta = [f64::INFINITY; 3];
+        for axis in 0..3 {
+            let cell = ((start.to_array()[axis] - grid_min[axis]) / size[axis]).floor() as isize;
+            voxel[axis] = cell.max(0).min(self.dims[axis] as isize - 1);
+            if dir[axis] > 0.0 {
+                step[axis] = 1;
+            } else if dir[axis] < 0.0 {
+                step[axis] = -1;
+            } else {
+                continue;
+            }
+            let next = voxel[axis] + (step[axis] > 0) as isize;
+            let boundary = grid_min[axis] + next as f64 * size[axis];
+            t_max[axis] = (boundary - origin[axis]) / dir[axis];
+            t_delta[axis] = size[axis] / dir[axis].abs();
+        }
+
+        // Crossing between a filled and an empty cell counts as a hit in either direction, so that
+        // rays which start inside of the solid part of the grid strike its surface on the way out.
+        let was_filled = self.filled_at(voxel);
+        if started_outside && was_filled {
+            return Some(self.hit_data(start, t_start, enter_axis, -dir[enter_axis].signum()));
+        }
+
+        loop {
+            let axis = if t_max[0] < t_max[1] {
+                if t_max[0] < t_max[2] {
+                    0
+                } else {
+                    2
+                }
+            } else if t_max[1] < t_max[2] {
+                1
+            } else {
+                2
+            };
+
+            let t = t_max[axis];
+            voxel[axis] += step[axis];
+            t_max[axis] += t_delta[axis];
+
+            let filled = self.filled_at(voxel);
+            if filled != was_filled {
+                let normal_sign = if filled { -step[axis] } else { step[axis] } as f64;
+                return Some(self.hit_data(ray.point + ray.dir * t, t, axis, normal_sign));
+            }
+            if voxel[axis] < 0 || voxel[axis] >= self.dims[axis] as isize {
+                return None;
+            }
+        }
+    }
+
+    fn paths(&self) -> Vec<LineSegment<WorldSpace>> {
+        // Collect unit-length edges of the cell lattice as (direction, starting lattice point).
+        let mut edges: HashSet<(usize, [isize; 3])> = HashSet::new();
+
+        for x in 0..self.dims[0] {
+            for y in 0..self.dims[1] {
+                for z in 0..self.dims[2] {
+                    let voxel = [x as isize, y as isize, z as isize];
+                    if !self.filled_at(voxel) {
+                        continue;
+                    }
+
+                    for axis in 0..3 {
+                        for &step in &[-1isize, 1] {
+                            if !self.exterior_face(voxel, axis, step) {
+                                continue;
+                            }
+
+                            // An edge of this face is part of the outline unless the face next to
+                            // it in the same plane is exposed in the same direction.
+                            for (across, along) in [
+                                ((axis + 1) % 3, (axis + 2) % 3),
+                                ((axis + 2) % 3, (axis + 1) % 3),
+                            ] {
+                                for &side in &[-1isize, 1] {
+                                    let mut neighbor = voxel;
+                                    neighbor[across] += side;
+                                    if self.exterior_face(neighbor, axis, step) {
+                                        continue;
+                                    }
+
+                                    let mut start = voxel;
+                                    start[axis] += (step > 0) as isize;
+                                    start[across] += (side > 0) as isize;
+                                    edges.insert((along, start));
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        // Merge runs of collinear unit edges into single lines.
+        let mut lines: BTreeMap<(usize, [isize; 3]), Vec<isize>> = BTreeMap::new();
+        for (along, start) in edges {
+            let mut key = start;
+            let position = key[along];
+            key[along] = 0;
+            lines.entry((along, key)).or_default().push(position);
+        }
+
+        let mut paths = Vec::new();
+        for ((along, key), mut positions) in lines {
+            positions.sort_unstable();
+            let mut run_start = positions[0];
+            for (i, position) in positions.iter().enumerate() {
+                let run_ends = positions.get(i + 1) != Some(&(position + 1));
+                if run_ends {
+                    let mut p1 = key;
+                    let mut p2 = key;
+                    p1[along] = run_start;
+                    p2[along] = position + 1;
+                    paths.push(LineSegment::tagged(
+                        self.lattice_point(p1),
+                        self.lattice_point(p2),
+                        self.tag,
+                    ));
+                    if let Some(next) = positions.get(i + 1) {
+                        run_start = *next;
+                    }
+                }
+            }
+        }
+
+        paths
+    }
+
+    fn bounding_box(&self) -> Option<crate::AABB<crate::WorldSpace>> {
+        let dims = WVec3::new(
+            self.dims[0] as f64,
+            self.dims[1] as f64,
+            self.dims[2] as f64,
+        );
+        Some(AABB::new(
+            self.origin,
+            self.origin + self.voxel_size.component_mul(dims),
+        ))
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        let size = self.voxel_size.to_array();
+        if size.iter().all(|s| *s > 0.0 && s.is_finite()) {
+            Ok(())
+        } else {
+            Err(format!(
+                "its voxel size of {:?} isn't positive along every axis",
+                size
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn grid(filled: &[[usize; 3]]) -> VoxelGrid {
+        let mut grid = VoxelGrid::new(WPoint3::origin(), WVec3::splat(1.0), [4, 4, 4]);
+        for voxel in filled {
+            grid.set(*voxel, true);
+        }
+        grid
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(grid(&[[1, 1, 1]]).validate().is_ok());
+        let flat = VoxelGrid::new(WPoint3::origin(), WVec3::new(1.0, 0.0, 1.0), [4, 4, 4]);
+        assert!(flat.validate().unwrap_err().contains("voxel size"));
+        let inverted = VoxelGrid::new(WPoint3::origin(), WVec3::splat(-1.0), [4, 4, 4]);
+        assert!(inverted.validate().unwrap_err().contains("voxel size"));
+    }
+
+    #[test]
+    fn test_voxel_hit_by() {
+        let grid = grid(&[[1, 1, 1], [2, 1, 1], [3, 3, 3]]);
+
+        assert_eq!(
+            grid.hit_by(&Ray::new(
+                WPoint3::new(-1.0, 1.5, 1.5),
+                WVec3::new(1.0, 0.0, 0.0)
+            )),
+            Some(HitData::new(WPoint3::new(1.0, 1.5, 1.5), 2.0))
+        );
+
+        assert_eq!(
+            grid.hit_by(&Ray::new(
+                WPoint3::new(2.5, 1.5, 10.0),
+                WVec3::new(0.0, 0.0, -1.0)
+            )),
+            Some(HitData::new(WPoint3::new(2.5, 1.5, 2.0), 8.0))
+        );
+
+        // enters the grid through a filled cell
+        assert_eq!(
+            grid.hit_by(&Ray::new(
+                WPoint3::new(3.5, 3.5, 6.0),
+                WVec3::new(0.0, 0.0, -1.0)
+            )),
+            Some(HitData::new(WPoint3::new(3.5, 3.5, 4.0), 2.0))
+        );
+
+        let origin = WPoint3::new(0.5, 0.5, 0.5);
+        let target = WPoint3::new(3.5, 3.5, 3.5);
+        assert_eq!(
+            grid.hit_by(&Ray::normalize_new(origin, target - origin)),
+            Some(HitData::new(
+                WPoint3::new(1.0, 1.0, 1.0),
+                (WPoint3::new(1.0, 1.0, 1.0) - origin).length()
+            ))
+        );
+
+        // passes between the filled cells
+        assert_eq!(
+            grid.hit_by(&Ray::new(
+                WPoint3::new(-1.0, 2.5, 1.5),
+                WVec3::new(1.0, 0.0, 0.0)
+            )),
+            None
+        );
+
+        // starts on the surface and leaves it
+        assert_eq!(
+            grid.hit_by(&Ray::new(
+                WPoint3::new(3.0, 1.5, 1.5),
+                WVec3::new(1.0, 0.0, 0.0)
+            )),
+            None
+        );
+
+        // starts inside of a filled cell and strikes the surface on the way out
+        let hit = grid.hit_by(&Ray::new(
+            WPoint3::new(2.5, 1.5, 1.5),
+            WVec3::new(0.0, 1.0, 0.0),
+        ));
+        assert_eq!(hit, Some(HitData::new(WPoint3::new(2.5, 2.0, 1.5), 0.5)));
+        assert_eq!(hit.map(|hit| hit.normal), Some(WVec3::new(0.0, 1.0, 0.0)));
+
+        // leaves through the boundary of the grid
+        assert_eq!(
+            grid.hit_by(&Ray::new(
+                WPoint3::new(3.5, 3.5, 3.5),
+                WVec3::new(1.0, 0.0, 0.0)
+            )),
+            Some(HitData::new(WPoint3::new(4.0, 3.5, 3.5), 0.5))
+        );
+
+        // rays without a direction miss rather than never leaving the cell they start in
+        for dir in [WVec3::zero(), WVec3::new(f64::NAN, 0.0, 0.0)] {
+            let ray = Ray {
+                point: WPoint3::new(0.5, 0.5, 0.5),
+                dir,
+                tmin: 0.0,
+                tmax: f64::INFINITY,
+            };
+            assert_eq!(grid.hit_by(&ray), None);
+            assert_eq!(ray.hit(&grid), None);
+        }
+    }
+
+    #[test]
+    fn test_voxel_paths() {
+        assert_eq!(grid(&[[0, 0, 0]]).paths().len(), 12);
+
+        // a row of cells is drawn like a single box
+        assert_eq!(grid(&[[0, 0, 0], [1, 0, 0], [2, 0, 0]]).paths().len(), 12);
+        assert_eq!(
+            grid(&[[0, 0, 0], [1, 0, 0], [0, 1, 0], [1, 1, 0]])
+                .paths()
+                .len(),
+            12
+        );
+
+        // an L-shaped prism
+        assert_eq!(grid(&[[0, 0, 0], [1, 0, 0], [0, 1, 0]]).paths().len(), 18);
+    }
+}